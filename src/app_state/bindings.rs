@@ -0,0 +1,276 @@
+use super::config_lines;
+use crate::events::*;
+use std::collections::HashMap;
+use std::error::Error;
+
+pub static BANK_SELECT_MSB: u8 = 0;
+pub static BANK_SELECT_LSB: u8 = 32;
+
+static BINDABLE_KEYS: [Key; 8] = [
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Num0,
+    Key::B,
+    Key::N,
+    Key::M,
+];
+
+pub enum Action {
+    ControlChange { control: u8, value: u8 },
+    ControlToggle { control: u8, on: u8, off: u8 },
+    ControlIncrement { control: u8, step: u8 },
+    ControlDecrement { control: u8, step: u8 },
+    ProgramChange(u8),
+    ProgramIncrement,
+    ProgramDecrement,
+    BankSelect { msb: u8, lsb: u8, program: Option<u8> },
+}
+
+pub struct Binding {
+    pub channel: Option<u8>,
+    pub action: Action,
+}
+
+pub struct Bindings {
+    bindings: HashMap<Key, Binding>,
+    controls: HashMap<(u8, u8), u8>,
+    programs: HashMap<u8, u8>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        let mut bindings = Bindings {
+            bindings: HashMap::new(),
+            controls: HashMap::new(),
+            programs: HashMap::new(),
+        };
+
+        bindings.bind(
+            Key::B,
+            Binding {
+                channel: None,
+                action: Action::ControlDecrement {
+                    control: 74,
+                    step: 8,
+                },
+            },
+        );
+
+        bindings.bind(
+            Key::N,
+            Binding {
+                channel: None,
+                action: Action::ControlIncrement {
+                    control: 74,
+                    step: 8,
+                },
+            },
+        );
+
+        bindings.bind(
+            Key::M,
+            Binding {
+                channel: None,
+                action: Action::ProgramIncrement,
+            },
+        );
+
+        return bindings;
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut bindings = Bindings {
+            bindings: HashMap::new(),
+            controls: HashMap::new(),
+            programs: HashMap::new(),
+        };
+
+        for line in config_lines(path)? {
+            let line = line.as_str();
+
+            let (key, binding) =
+                parse_binding(line).map_err(|err| format!("invalid binding `{}`: {}", line, err))?;
+
+            bindings.bind(key, binding);
+        }
+
+        return Ok(bindings);
+    }
+
+    pub fn bind(&mut self, key: Key, binding: Binding) {
+        self.bindings.insert(key, binding);
+    }
+
+    pub fn label(&self, key: &Key) -> Option<String> {
+        return self.bindings.get(key).map(|binding| binding.action.label());
+    }
+
+    pub fn trigger(&mut self, key: &Key, default_channel: u8) -> Vec<Event> {
+        let binding = match self.bindings.get(key) {
+            Some(binding) => binding,
+            None => return Vec::new(),
+        };
+
+        let channel = binding.channel.unwrap_or(default_channel);
+
+        return match binding.action {
+            Action::ControlChange { control, value } => {
+                self.controls.insert((channel, control), value);
+
+                vec![control_change(channel, control, value)]
+            }
+            Action::ControlToggle { control, on, off } => {
+                let value = match self.controls.get(&(channel, control)) {
+                    Some(value) if *value == on => off,
+                    _ => on,
+                };
+
+                self.controls.insert((channel, control), value);
+
+                vec![control_change(channel, control, value)]
+            }
+            Action::ControlIncrement { control, step } => {
+                let value = self.controls.entry((channel, control)).or_insert(64);
+                *value = (*value as u32 + step as u32).min(127) as u8;
+
+                vec![control_change(channel, control, *value)]
+            }
+            Action::ControlDecrement { control, step } => {
+                let value = self.controls.entry((channel, control)).or_insert(64);
+                *value = value.saturating_sub(step);
+
+                vec![control_change(channel, control, *value)]
+            }
+            Action::ProgramChange(program) => {
+                self.programs.insert(channel, program);
+
+                vec![Event::ProgramChange { channel, program }]
+            }
+            Action::ProgramIncrement => {
+                let program = self.programs.entry(channel).or_insert(0);
+                *program = (*program + 1) % 128;
+
+                vec![Event::ProgramChange {
+                    channel,
+                    program: *program,
+                }]
+            }
+            Action::ProgramDecrement => {
+                let program = self.programs.entry(channel).or_insert(0);
+                *program = (*program + 127) % 128;
+
+                vec![Event::ProgramChange {
+                    channel,
+                    program: *program,
+                }]
+            }
+            Action::BankSelect { msb, lsb, program } => {
+                let mut events = vec![
+                    control_change(channel, BANK_SELECT_MSB, msb),
+                    control_change(channel, BANK_SELECT_LSB, lsb),
+                ];
+
+                let program = program.unwrap_or(*self.programs.get(&channel).unwrap_or(&0));
+                self.programs.insert(channel, program);
+                events.push(Event::ProgramChange { channel, program });
+
+                events
+            }
+        };
+    }
+}
+
+impl Action {
+    pub fn label(&self) -> String {
+        return match self {
+            Action::ControlChange { control, value } => format!("cc{}={}", control, value),
+            Action::ControlToggle { control, .. } => format!("cc{}", control),
+            Action::ControlIncrement { control, .. } => format!("cc{}+", control),
+            Action::ControlDecrement { control, .. } => format!("cc{}-", control),
+            Action::ProgramChange(program) => format!("prg {}", program),
+            Action::ProgramIncrement => "prg+".into(),
+            Action::ProgramDecrement => "prg-".into(),
+            Action::BankSelect { msb, lsb, .. } => format!("bank {}:{}", msb, lsb),
+        };
+    }
+}
+
+// A binding line reads `<key> [ch<channel>] <action> <args>`, for example
+// `B cc- 74 8`, `N ch2 cc+ 74 8` or `Num6 bank 0 1 12`.
+fn parse_binding(line: &str) -> Result<(Key, Binding), String> {
+    let mut words = line.split_whitespace().peekable();
+
+    let name = words.next().ok_or("missing key")?;
+    let key = BINDABLE_KEYS
+        .iter()
+        .find(|key| format!("{}", key) == name)
+        .ok_or_else(|| format!("{} is not a bindable key", name))?
+        .clone();
+
+    let channel = match words.peek() {
+        Some(word) if word.starts_with("ch") => {
+            let channel = match word[2..].parse::<u8>() {
+                Ok(channel) if (1..=16).contains(&channel) => channel - 1,
+                _ => return Err(format!("channel {} is outside 1..=16", &word[2..])),
+            };
+
+            words.next();
+            Some(channel)
+        }
+        _ => None,
+    };
+
+    let action = words.next().ok_or("missing action")?;
+    let args = words
+        .map(|word| match word.parse::<u8>() {
+            Ok(value) if value <= 127 => Ok(value),
+            _ => Err(format!("{} is not a MIDI data byte (0..=127)", word)),
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    let action = match (action, args.as_slice()) {
+        ("cc", [control, value]) => Action::ControlChange {
+            control: *control,
+            value: *value,
+        },
+        ("toggle", [control, on, off]) => Action::ControlToggle {
+            control: *control,
+            on: *on,
+            off: *off,
+        },
+        ("cc+", [control, step]) => Action::ControlIncrement {
+            control: *control,
+            step: *step,
+        },
+        ("cc-", [control, step]) => Action::ControlDecrement {
+            control: *control,
+            step: *step,
+        },
+        ("program", [program]) => Action::ProgramChange(*program),
+        ("program+", []) => Action::ProgramIncrement,
+        ("program-", []) => Action::ProgramDecrement,
+        ("bank", [msb, lsb]) => Action::BankSelect {
+            msb: *msb,
+            lsb: *lsb,
+            program: None,
+        },
+        ("bank", [msb, lsb, program]) => Action::BankSelect {
+            msb: *msb,
+            lsb: *lsb,
+            program: Some(*program),
+        },
+        _ => return Err(format!("unknown action {} or wrong arguments", action)),
+    };
+
+    return Ok((key, Binding { channel, action }));
+}
+
+fn control_change(channel: u8, control: u8, value: u8) -> Event {
+    return Event::ControlChange {
+        channel,
+        control,
+        value,
+    };
+}
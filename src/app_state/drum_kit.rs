@@ -0,0 +1,142 @@
+use super::config_lines;
+use crate::events::*;
+use std::collections::HashMap;
+use std::error::Error;
+
+// General MIDI percussion lives on channel 10.
+pub static DRUM_CHANNEL: u8 = 9;
+
+static PADS: [Key; 17] = [
+    Key::W,
+    Key::E,
+    Key::R,
+    Key::T,
+    Key::Y,
+    Key::U,
+    Key::I,
+    Key::O,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::J,
+    Key::K,
+    Key::L,
+];
+
+static INSTRUMENTS: [&str; 47] = [
+    "Acoustic Kick",
+    "Kick",
+    "Side Stick",
+    "Snare",
+    "Clap",
+    "Electric Snare",
+    "Low Floor Tom",
+    "Closed HH",
+    "High Floor Tom",
+    "Pedal HH",
+    "Low Tom",
+    "Open HH",
+    "Low Mid Tom",
+    "High Mid Tom",
+    "Crash",
+    "High Tom",
+    "Ride",
+    "China",
+    "Ride Bell",
+    "Tambourine",
+    "Splash",
+    "Cowbell",
+    "Crash 2",
+    "Vibraslap",
+    "Ride 2",
+    "High Bongo",
+    "Low Bongo",
+    "Mute Conga",
+    "Open Conga",
+    "Low Conga",
+    "High Timbale",
+    "Low Timbale",
+    "High Agogo",
+    "Low Agogo",
+    "Cabasa",
+    "Maracas",
+    "Short Whistle",
+    "Long Whistle",
+    "Short Guiro",
+    "Long Guiro",
+    "Claves",
+    "High Wood Block",
+    "Low Wood Block",
+    "Mute Cuica",
+    "Open Cuica",
+    "Mute Triangle",
+    "Open Triangle",
+];
+
+static FIRST_INSTRUMENT: u8 = 35;
+
+pub fn instrument_name(note: u8) -> Option<&'static str> {
+    return INSTRUMENTS
+        .get(note.checked_sub(FIRST_INSTRUMENT)? as usize)
+        .copied();
+}
+
+fn instrument_note(name: &str) -> Option<u8> {
+    if let Ok(note) = name.parse::<u8>() {
+        return if note <= 127 { Some(note) } else { None };
+    }
+
+    return INSTRUMENTS
+        .iter()
+        .position(|instrument| instrument.eq_ignore_ascii_case(name))
+        .map(|index| index as u8 + FIRST_INSTRUMENT);
+}
+
+pub struct DrumKit {
+    pads: HashMap<Key, u8>,
+}
+
+impl DrumKit {
+    pub fn new() -> Self {
+        let notes = [42, 44, 46, 54, 56, 53, 55, 70, 36, 38, 39, 37, 45, 47, 50, 49, 51];
+
+        return DrumKit {
+            pads: PADS.iter().cloned().zip(notes.iter().cloned()).collect(),
+        };
+    }
+
+    // A layout line reads `<key> <note or instrument name>`, for example
+    // `A Kick`, `S 38` or `W Closed HH`.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut pads = HashMap::new();
+
+        for line in config_lines(path)? {
+            let line = line.as_str();
+
+            let mut words = line.splitn(2, char::is_whitespace);
+
+            let key = words.next().and_then(|name| {
+                PADS.iter().find(|key| format!("{}", key) == name)
+            });
+            let note = words.next().and_then(|name| instrument_note(name.trim()));
+
+            match (key, note) {
+                (Some(key), Some(note)) => pads.insert(key.clone(), note),
+                _ => return Err(format!("invalid drum pad: {}", line).into()),
+            };
+        }
+
+        return Ok(DrumKit { pads });
+    }
+
+    pub fn note(&self, key: &Key) -> Option<u8> {
+        return self.pads.get(key).cloned();
+    }
+
+    pub fn label(&self, key: &Key) -> Option<&'static str> {
+        return self.note(key).and_then(instrument_name);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::events::*;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Exercise {
+    Degree,
+    Interval,
+    Chord,
+}
+
+// Chord qualities as semitones above the root, answered on `CHORD_KEYS`.
+pub static QUALITIES: [(&str, [u32; 3]); 4] = [
+    ("maj", [0, 4, 7]),
+    ("min", [0, 3, 7]),
+    ("dim", [0, 3, 6]),
+    ("aug", [0, 4, 8]),
+];
+
+static CHORD_KEYS: [Key; 4] = [Key::A, Key::S, Key::D, Key::F];
+
+static DEGREE_ORDER: [i32; 7] = [0, 4, 2, 3, 1, 5, 6];
+
+static MAX_LEVEL: usize = 5;
+
+pub struct EarTraining {
+    pub exercise: Option<Exercise>,
+    pub question: Option<i32>,
+    pub level: usize,
+    pub correct: u32,
+    pub answered: u32,
+    streak: i32,
+    flash: Vec<(Key, bool)>,
+    flash_until: Option<Instant>,
+}
+
+impl EarTraining {
+    pub fn new() -> Self {
+        return EarTraining {
+            exercise: None,
+            question: None,
+            level: 1,
+            correct: 0,
+            answered: 0,
+            streak: 0,
+            flash: Vec::new(),
+            flash_until: None,
+        };
+    }
+
+    pub fn toggle(&mut self) {
+        self.exercise = match self.exercise {
+            None => Some(Exercise::Degree),
+            Some(Exercise::Degree) => Some(Exercise::Interval),
+            Some(Exercise::Interval) => Some(Exercise::Chord),
+            Some(Exercise::Chord) => None,
+        };
+
+        self.question = None;
+        self.level = 1;
+        self.correct = 0;
+        self.answered = 0;
+        self.streak = 0;
+    }
+
+    // The possible answers at the current level, easiest first.
+    pub fn choices(&self, scale_length: i32) -> Vec<i32> {
+        return match self.exercise {
+            Some(Exercise::Degree) => DEGREE_ORDER
+                .iter()
+                .cloned()
+                .filter(|degree| *degree < scale_length)
+                .take(self.level + 2)
+                .collect(),
+            Some(Exercise::Interval) => (1..=(self.level as i32 + 2).min(7)).collect(),
+            Some(Exercise::Chord) => (0..(self.level + 1).min(QUALITIES.len()) as i32).collect(),
+            None => Vec::new(),
+        };
+    }
+
+    pub fn chord_key(&self, quality: i32) -> Option<Key> {
+        return CHORD_KEYS.get(quality as usize).cloned();
+    }
+
+    // Scores an answer and moves the level up after three correct answers
+    // in a row, or down after two misses in a row.
+    pub fn answer(&mut self, pressed: Key, correct: bool, expected: Option<Key>) {
+        self.question = None;
+        self.answered += 1;
+
+        self.flash = vec![(pressed, correct)];
+        self.flash_until = Some(Instant::now() + Duration::from_millis(600));
+
+        if correct {
+            self.correct += 1;
+            self.streak = self.streak.max(0) + 1;
+        } else {
+            self.streak = self.streak.min(0) - 1;
+
+            if let Some(expected) = expected {
+                self.flash.push((expected, true));
+            }
+        }
+
+        if self.streak >= 3 {
+            self.level = (self.level + 1).min(MAX_LEVEL);
+            self.streak = 0;
+        }
+
+        if self.streak <= -2 {
+            self.level = (self.level - 1).max(1);
+            self.streak = 0;
+        }
+    }
+
+    pub fn flash(&self, key: &Key) -> Option<bool> {
+        match self.flash_until {
+            Some(until) if until > Instant::now() => {}
+            _ => return None,
+        }
+
+        return self
+            .flash
+            .iter()
+            .find(|(flashed, _)| flashed == key)
+            .map(|(_, correct)| *correct);
+    }
+
+    pub fn key_label(&self, key: &Key) -> Option<String> {
+        if self.exercise != Some(Exercise::Chord) {
+            return None;
+        }
+
+        let quality = CHORD_KEYS.iter().position(|chord_key| chord_key == key)?;
+
+        return Some(QUALITIES[quality].0.into());
+    }
+
+    pub fn label(&self) -> String {
+        let exercise = match self.exercise {
+            Some(Exercise::Degree) => "Degrees",
+            Some(Exercise::Interval) => "Intervals",
+            Some(Exercise::Chord) => "Chords",
+            None => return "".into(),
+        };
+
+        return format!(
+            "Ear {} / level {} / {} of {}",
+            exercise, self.level, self.correct, self.answered
+        );
+    }
+}
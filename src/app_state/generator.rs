@@ -0,0 +1,115 @@
+#[derive(Clone, Copy)]
+pub enum StepProfile {
+    Stepwise,
+    Mixed,
+    Leaping,
+}
+
+impl StepProfile {
+    // Relative weights of moving 0, 1, 2, 3 or 4 scale steps.
+    pub fn weights(&self) -> [u32; 5] {
+        return match self {
+            StepProfile::Stepwise => [1, 10, 3, 1, 0],
+            StepProfile::Mixed => [1, 6, 4, 2, 1],
+            StepProfile::Leaping => [0, 3, 3, 3, 3],
+        };
+    }
+
+    pub fn label(&self) -> &str {
+        return match self {
+            StepProfile::Stepwise => "stepwise",
+            StepProfile::Mixed => "mixed",
+            StepProfile::Leaping => "leaping",
+        };
+    }
+}
+
+pub struct Generator {
+    pub enabled: bool,
+    pub seed: Option<u64>,
+    pub steps: StepProfile,
+    pub density: f64,
+    pub rest_probability: f64,
+    pub octave: i32,
+    pub span: i32,
+}
+
+impl Generator {
+    pub fn new() -> Self {
+        return Generator {
+            enabled: false,
+            seed: None,
+            steps: StepProfile::Stepwise,
+            density: 0.5,
+            rest_probability: 0.2,
+            octave: 0,
+            span: 10,
+        };
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn toggle_steps(&mut self) {
+        self.steps = match self.steps {
+            StepProfile::Stepwise => StepProfile::Mixed,
+            StepProfile::Mixed => StepProfile::Leaping,
+            StepProfile::Leaping => StepProfile::Stepwise,
+        };
+    }
+
+    pub fn increase_density(&mut self) {
+        self.density = (self.density + 0.1).min(1.0);
+    }
+
+    pub fn decrease_density(&mut self) {
+        self.density = (self.density - 0.1).max(0.1);
+    }
+
+    pub fn increase_rests(&mut self) {
+        self.rest_probability = (self.rest_probability + 0.1).min(0.9);
+    }
+
+    pub fn decrease_rests(&mut self) {
+        self.rest_probability = (self.rest_probability - 0.1).max(0.0);
+    }
+
+    pub fn widen_span(&mut self) {
+        self.span = (self.span + 1).min(21);
+    }
+
+    pub fn narrow_span(&mut self) {
+        self.span = (self.span - 1).max(3);
+    }
+
+    pub fn raise_register(&mut self) {
+        self.octave = (self.octave + 1).min(2);
+    }
+
+    pub fn lower_register(&mut self) {
+        self.octave = (self.octave - 1).max(-2);
+    }
+
+    // The lowest and highest scale positions the melody may reach.
+    pub fn register(&self, scale_length: i32) -> (i32, i32) {
+        let low = self.octave * scale_length;
+
+        return (low, low + self.span);
+    }
+
+    pub fn label(&self) -> String {
+        if !self.enabled {
+            return "".into();
+        }
+
+        return format!(
+            "Melody {} / {:.0}% / rest {:.0}% / {:+} o / span {}",
+            self.steps.label(),
+            self.density * 100.0,
+            self.rest_probability * 100.0,
+            self.octave,
+            self.span
+        );
+    }
+}
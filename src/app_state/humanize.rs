@@ -0,0 +1,33 @@
+#[derive(Clone, Copy)]
+pub struct Humanize {
+    pub enabled: bool,
+    pub velocity_range: u8,
+    pub timing_range: u64,
+    pub seed: Option<u64>,
+}
+
+impl Humanize {
+    pub fn new() -> Self {
+        return Humanize {
+            enabled: false,
+            velocity_range: 12,
+            timing_range: 20,
+            seed: None,
+        };
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn label(&self) -> String {
+        if !self.enabled {
+            return "".into();
+        }
+
+        return format!(
+            "Humanize ±{} / {}ms",
+            self.velocity_range, self.timing_range
+        );
+    }
+}
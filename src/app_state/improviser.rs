@@ -0,0 +1,173 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+// A played note as a scale degree and the ticks until the next note.
+pub type Token = (i32, u64);
+
+pub static MAX_ORDER: usize = 3;
+
+pub struct MarkovModel {
+    transitions: HashMap<Vec<Token>, HashMap<Token, u32>>,
+}
+
+impl MarkovModel {
+    pub fn new() -> Self {
+        return MarkovModel {
+            transitions: HashMap::new(),
+        };
+    }
+
+    // Each line holds one transition count as `<context> > <token> <count>`,
+    // with tokens written as `<degree>:<ticks>`, for example `0:6 2:6 > 4:12 3`.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut model = MarkovModel::new();
+
+        for line in fs::read_to_string(path)?.lines() {
+            let (context, token, count) = parse_transition(line)
+                .ok_or_else(|| format!("invalid transition: {}", line))?;
+
+            model
+                .transitions
+                .entry(context)
+                .or_default()
+                .insert(token, count);
+        }
+
+        return Ok(model);
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut lines = Vec::new();
+
+        for (context, next) in &self.transitions {
+            let context: Vec<String> = context.iter().map(format_token).collect();
+
+            for (token, count) in next {
+                lines.push(format!("{} > {} {}", context.join(" "), format_token(token), count));
+            }
+        }
+
+        lines.sort();
+        fs::write(path, lines.join("\n") + "\n")?;
+
+        return Ok(());
+    }
+
+    // Counts `token` after every suffix of `context` up to `MAX_ORDER`, so
+    // the model can fall back to shorter contexts when sampling.
+    pub fn train(&mut self, context: &[Token], token: Token) {
+        for order in 0..=MAX_ORDER.min(context.len()) {
+            let suffix = context[context.len() - order..].to_vec();
+
+            *self
+                .transitions
+                .entry(suffix)
+                .or_default()
+                .entry(token)
+                .or_insert(0) += 1;
+        }
+    }
+
+    pub fn sample<R: Rng>(&self, context: &[Token], order: usize, rng: &mut R) -> Option<Token> {
+        for order in (0..=order.min(context.len())).rev() {
+            let next = match self.transitions.get(&context[context.len() - order..]) {
+                Some(next) => next,
+                None => continue,
+            };
+
+            let mut candidates: Vec<(&Token, &u32)> = next.iter().collect();
+            candidates.sort();
+
+            let total: u32 = candidates.iter().map(|(_, count)| **count).sum();
+
+            if total == 0 {
+                continue;
+            }
+            let mut pick = rng.gen_range(0, total);
+
+            for (token, count) in candidates {
+                if pick < *count {
+                    return Some(*token);
+                }
+
+                pick -= count;
+            }
+        }
+
+        return None;
+    }
+
+    pub fn notes(&self) -> u32 {
+        return match self.transitions.get(&Vec::new()) {
+            Some(next) => next.values().sum(),
+            None => 0,
+        };
+    }
+}
+
+pub struct Improviser {
+    pub enabled: bool,
+    pub order: usize,
+    pub model: MarkovModel,
+}
+
+impl Improviser {
+    pub fn new() -> Self {
+        return Improviser {
+            enabled: false,
+            order: 2,
+            model: MarkovModel::new(),
+        };
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn toggle_order(&mut self) {
+        self.order = self.order % MAX_ORDER + 1;
+    }
+
+    pub fn label(&self) -> String {
+        if !self.enabled {
+            return "".into();
+        }
+
+        return format!(
+            "Improviser order {} / {} notes",
+            self.order,
+            self.model.notes()
+        );
+    }
+}
+
+fn format_token(token: &Token) -> String {
+    return format!("{}:{}", token.0, token.1);
+}
+
+fn parse_token(word: &str) -> Option<Token> {
+    let mut parts = word.split(':');
+
+    let degree = parts.next()?.parse().ok()?;
+    let ticks = parts.next()?.parse().ok()?;
+
+    return Some((degree, ticks));
+}
+
+fn parse_transition(line: &str) -> Option<(Vec<Token>, Token, u32)> {
+    let mut sides = line.split('>');
+
+    let context = sides
+        .next()?
+        .split_whitespace()
+        .map(parse_token)
+        .collect::<Option<Vec<Token>>>()?;
+
+    let mut words = sides.next()?.split_whitespace();
+    let token = parse_token(words.next()?)?;
+    let count = words.next()?.parse().ok().filter(|count| *count > 0)?;
+
+    return Some((context, token, count));
+}
@@ -0,0 +1,44 @@
+use crate::scale::Scale;
+
+static WHITE_KEYS: [u32; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+#[derive(Clone, Copy)]
+pub enum InputMapping {
+    WhiteKeys,
+    Nearest,
+}
+
+impl InputMapping {
+    pub fn toggle(&mut self) {
+        *self = match self {
+            InputMapping::WhiteKeys => InputMapping::Nearest,
+            InputMapping::Nearest => InputMapping::WhiteKeys,
+        };
+    }
+
+    pub fn map(&self, scale: &Box<dyn Scale + Sync + Send>, note: u8) -> Option<u32> {
+        return match self {
+            InputMapping::WhiteKeys => {
+                let position = WHITE_KEYS.iter().position(|n| *n == note as u32 % 12)?;
+                let degree = (note as i32 / 12 - 5) * 7 + position as i32;
+
+                Some(scale.note(degree))
+            }
+            InputMapping::Nearest => {
+                let length = scale.notes().len() as i32;
+                let octave = (note as i32 - scale.note(0) as i32).div_euclid(12);
+
+                (octave * length - length..octave * length + 2 * length)
+                    .map(|degree| scale.note(degree))
+                    .min_by_key(|candidate| (*candidate as i32 - note as i32).abs())
+            }
+        };
+    }
+
+    pub fn label(&self) -> String {
+        return match self {
+            InputMapping::WhiteKeys => "White Keys".into(),
+            InputMapping::Nearest => "Nearest".into(),
+        };
+    }
+}
@@ -1,17 +1,109 @@
+mod bindings;
+mod drum_kit;
+mod ear_training;
+mod generator;
+mod humanize;
+mod improviser;
+mod input_mapping;
+mod mono;
+mod note_repeat;
+mod polyphony;
+mod progressions;
+mod rhythm;
+mod routing;
+mod row_layout;
+mod song_follow;
+mod split;
+mod velocity;
+
 use crate::events::*;
 use crate::scale::Scale;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+
+pub use self::bindings::Bindings;
+pub use self::drum_kit::{DrumKit, DRUM_CHANNEL};
+pub use self::ear_training::{EarTraining, Exercise, QUALITIES};
+pub use self::generator::Generator;
+pub use self::humanize::Humanize;
+pub use self::improviser::{Improviser, MarkovModel, Token, MAX_ORDER};
+pub use self::input_mapping::InputMapping;
+pub use self::mono::{Mono, NotePriority};
+pub use self::note_repeat::NoteRepeat;
+pub use self::polyphony::{Polyphony, StealPolicy};
+pub use self::progressions::{ProgressionMode, Progressions};
+pub use self::rhythm::Rhythm;
+pub use self::routing::ChannelRouting;
+pub use self::row_layout::{Row, RowLayout};
+pub use self::song_follow::{FollowMode, SongFollow};
+pub use self::split::Split;
+pub use self::velocity::Velocity;
 
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub enum PlayMode {
     Single,
     Chord,
     Chord7,
+    Mono,
+    Drums,
+}
+
+impl PlayMode {
+    pub fn next(&self) -> PlayMode {
+        return match self {
+            PlayMode::Single => PlayMode::Chord,
+            PlayMode::Chord => PlayMode::Chord7,
+            PlayMode::Chord7 => PlayMode::Mono,
+            PlayMode::Mono => PlayMode::Drums,
+            PlayMode::Drums => PlayMode::Single,
+        };
+    }
+
+    pub fn label(&self) -> String {
+        return match self {
+            PlayMode::Single => "Single Note".into(),
+            PlayMode::Chord => "Chord".into(),
+            PlayMode::Chord7 => "Chord7".into(),
+            PlayMode::Mono => "Mono".into(),
+            PlayMode::Drums => "Drums".into(),
+        };
+    }
+}
+
+pub enum LatchMode {
+    Off,
+    Latch,
+    Exclusive,
 }
 
 pub struct AppState {
     pub scale: Box<dyn Scale + Sync + Send>,
     pub play_mode: PlayMode,
+    pub latch_mode: LatchMode,
+    pub row_layout: RowLayout,
+    pub routing: ChannelRouting,
+    pub split: Split,
+    pub mono: Mono,
+    pub polyphony: Polyphony,
+    pub bindings: Bindings,
+    pub drum_kit: DrumKit,
+    pub progressions: Progressions,
+    pub generator: Generator,
+    pub improviser: Improviser,
+    pub ear_training: EarTraining,
+    pub song_follow: SongFollow,
+    pub rhythm: Rhythm,
+    pub midi_input: Option<String>,
+    pub input_mapping: InputMapping,
     pub pressed_keys: HashMap<Key, bool>,
+    pub latched_keys: HashSet<Key>,
+    pub sustain: bool,
+    pub sustained_notes: HashSet<u32>,
+    pub velocity: Velocity,
+    pub humanize: Humanize,
+    pub tempo: u32,
+    pub note_repeat: NoteRepeat,
     pub screen_width: u32,
     pub screen_height: u32,
 }
@@ -21,9 +113,32 @@ impl AppState {
         return AppState {
             scale,
             play_mode: PlayMode::Single,
+            latch_mode: LatchMode::Off,
+            row_layout: RowLayout::Fifths,
+            routing: ChannelRouting::new(),
+            split: Split::new(),
+            mono: Mono::new(),
+            polyphony: Polyphony::new(),
+            bindings: Bindings::new(),
+            drum_kit: DrumKit::new(),
+            progressions: Progressions::new(),
+            generator: Generator::new(),
+            improviser: Improviser::new(),
+            ear_training: EarTraining::new(),
+            song_follow: SongFollow::new(),
+            rhythm: Rhythm::new(),
+            midi_input: None,
+            input_mapping: InputMapping::WhiteKeys,
             pressed_keys: HashMap::new(),
+            latched_keys: HashSet::new(),
+            sustain: false,
+            sustained_notes: HashSet::new(),
+            velocity: Velocity::new(),
+            humanize: Humanize::new(),
+            tempo: 120,
+            note_repeat: NoteRepeat::new(),
             screen_width: 800,
-            screen_height: 600,
+            screen_height: 700,
         };
     }
 
@@ -32,32 +147,142 @@ impl AppState {
     }
 
     pub fn toggle_play_mode(&mut self) {
-        match self.play_mode {
-            PlayMode::Single => self.play_mode = PlayMode::Chord,
-            PlayMode::Chord => self.play_mode = PlayMode::Chord7,
-            PlayMode::Chord7 => self.play_mode = PlayMode::Single,
-        }
+        self.play_mode = self.play_mode.next();
     }
 
-    pub fn play_chord(&self) -> bool {
-        return match self.play_mode {
-            PlayMode::Chord => true,
-            _ => false
+    pub fn play_mode_for(&self, row: Option<Row>) -> PlayMode {
+        return match self.split.zone(row) {
+            Some(zone) => zone.play_mode,
+            None => self.play_mode,
         };
     }
 
-    pub fn play_chord7(&self) -> bool {
-        return match self.play_mode {
-            PlayMode::Chord7 => true,
-            _ => false
+    pub fn channel_for(&self, row: Option<Row>, chord_tone: bool) -> u8 {
+        // Only the letter grid becomes drum pads; generated, progression and
+        // external notes keep their usual channel.
+        if row.is_some() && self.play_mode_for(row) == PlayMode::Drums {
+            return DRUM_CHANNEL;
+        }
+
+        return match self.split.zone(row) {
+            Some(zone) => zone.channel,
+            None => self
+                .routing
+                .channel_for(row, self.play_mode_for(row), chord_tone),
+        };
+    }
+
+    pub fn active_channels(&self) -> Vec<u8> {
+        let mut channels = self.routing.channels();
+
+        if self.split.enabled {
+            channels.extend(self.split.zones.values().map(|zone| zone.channel));
+            channels.sort();
+            channels.dedup();
+        }
+
+        return channels;
+    }
+
+    pub fn octave_for(&self, row: Option<Row>) -> i32 {
+        return match self.split.zone(row) {
+            Some(zone) => zone.octave,
+            None => 0,
         };
     }
 
+    // Each zone shows its own channel while the keyboard is split.
+    pub fn channel_label(&self) -> String {
+        if self.split.enabled {
+            return String::new();
+        }
+
+        return self.routing.label();
+    }
+
     pub fn play_mode_label(&self) -> String {
         return match self.play_mode {
-            PlayMode::Single => "Single Note".into(),
-            PlayMode::Chord => "Chord".into(),
-            PlayMode::Chord7 => "Chord7".into(),
+            PlayMode::Mono => format!("{} {}", self.play_mode.label(), self.mono.label()),
+            _ => self.play_mode.label(),
+        };
+    }
+
+    pub fn toggle_latch_mode(&mut self) {
+        match self.latch_mode {
+            LatchMode::Off => self.latch_mode = LatchMode::Latch,
+            LatchMode::Latch => self.latch_mode = LatchMode::Exclusive,
+            LatchMode::Exclusive => self.latch_mode = LatchMode::Off,
+        }
+    }
+
+    pub fn latch(&self) -> bool {
+        return match self.latch_mode {
+            LatchMode::Off => false,
+            _ => true,
+        };
+    }
+
+    pub fn exclusive_latch(&self) -> bool {
+        return match self.latch_mode {
+            LatchMode::Exclusive => true,
+            _ => false,
+        };
+    }
+
+    pub fn midi_input_label(&self) -> String {
+        return match &self.midi_input {
+            Some(name) => format!("{} ({})", name, self.input_mapping.label()),
+            None => "".into(),
+        };
+    }
+
+    // Bindings from bindings.conf win over the default progression pads.
+    pub fn is_progression_pad(&self, key: &Key) -> bool {
+        return self.progressions.is_pad(key) && self.bindings.label(key).is_none();
+    }
+
+    pub fn pad_label(&self, key: &Key) -> Option<String> {
+        return self
+            .ear_training
+            .key_label(key)
+            .or_else(|| self.bindings.label(key))
+            .or_else(|| self.progressions.pad_label(key));
+    }
+
+    pub fn latch_mode_label(&self) -> String {
+        return match self.latch_mode {
+            LatchMode::Off => "".into(),
+            LatchMode::Latch => "Latch".into(),
+            LatchMode::Exclusive => "Exclusive Latch".into(),
         };
     }
+
+    pub fn increase_tempo(&mut self, nr: u32) {
+        self.tempo = (self.tempo + nr).min(300);
+    }
+
+    pub fn decrease_tempo(&mut self, nr: u32) {
+        self.tempo = self.tempo.saturating_sub(nr).max(30);
+    }
+
+    pub fn tempo_label(&self) -> String {
+        let label = format!("{} BPM", self.tempo);
+        let repeat = self.note_repeat.label();
+
+        if repeat.is_empty() {
+            return label;
+        }
+
+        return format!("{} {}", label, repeat);
+    }
+}
+
+// Reads a config file, skipping blank lines and `#` comments.
+fn config_lines(path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    return Ok(fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect());
 }
@@ -0,0 +1,50 @@
+#[derive(Clone, Copy)]
+pub enum NotePriority {
+    Last,
+    Lowest,
+    Highest,
+}
+
+pub struct Mono {
+    pub priority: NotePriority,
+    pub portamento: Option<u8>,
+}
+
+impl Mono {
+    pub fn new() -> Self {
+        return Mono {
+            priority: NotePriority::Last,
+            portamento: None,
+        };
+    }
+
+    pub fn toggle_priority(&mut self) {
+        self.priority = match self.priority {
+            NotePriority::Last => NotePriority::Lowest,
+            NotePriority::Lowest => NotePriority::Highest,
+            NotePriority::Highest => NotePriority::Last,
+        };
+    }
+
+    pub fn toggle_portamento(&mut self) {
+        self.portamento = match self.portamento {
+            None => Some(32),
+            Some(32) => Some(64),
+            Some(64) => Some(96),
+            Some(_) => None,
+        };
+    }
+
+    pub fn label(&self) -> String {
+        let priority = match self.priority {
+            NotePriority::Last => "Last",
+            NotePriority::Lowest => "Lowest",
+            NotePriority::Highest => "Highest",
+        };
+
+        return match self.portamento {
+            Some(time) => format!("{} Note / Porta {}", priority, time),
+            None => format!("{} Note", priority),
+        };
+    }
+}
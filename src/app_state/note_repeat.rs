@@ -0,0 +1,116 @@
+#[derive(Clone, Copy)]
+pub enum RepeatRate {
+    Quarter,
+    QuarterTriplet,
+    Eighth,
+    EighthTriplet,
+    Sixteenth,
+    SixteenthTriplet,
+    ThirtySecond,
+    ThirtySecondTriplet,
+}
+
+#[derive(Clone, Copy)]
+pub enum Ramp {
+    Off,
+    Up,
+    Down,
+}
+
+static RAMP_STEPS: u32 = 8;
+
+pub struct NoteRepeat {
+    pub rate: Option<RepeatRate>,
+    pub ramp: Ramp,
+}
+
+impl RepeatRate {
+    pub fn ticks(&self) -> u64 {
+        return match self {
+            RepeatRate::Quarter => 24,
+            RepeatRate::QuarterTriplet => 16,
+            RepeatRate::Eighth => 12,
+            RepeatRate::EighthTriplet => 8,
+            RepeatRate::Sixteenth => 6,
+            RepeatRate::SixteenthTriplet => 4,
+            RepeatRate::ThirtySecond => 3,
+            RepeatRate::ThirtySecondTriplet => 2,
+        };
+    }
+
+    pub fn label(&self) -> String {
+        return match self {
+            RepeatRate::Quarter => "1/4".into(),
+            RepeatRate::QuarterTriplet => "1/4T".into(),
+            RepeatRate::Eighth => "1/8".into(),
+            RepeatRate::EighthTriplet => "1/8T".into(),
+            RepeatRate::Sixteenth => "1/16".into(),
+            RepeatRate::SixteenthTriplet => "1/16T".into(),
+            RepeatRate::ThirtySecond => "1/32".into(),
+            RepeatRate::ThirtySecondTriplet => "1/32T".into(),
+        };
+    }
+}
+
+impl NoteRepeat {
+    pub fn new() -> Self {
+        return NoteRepeat {
+            rate: None,
+            ramp: Ramp::Off,
+        };
+    }
+
+    pub fn toggle_rate(&mut self) {
+        self.rate = match self.rate {
+            None => Some(RepeatRate::Quarter),
+            Some(RepeatRate::Quarter) => Some(RepeatRate::QuarterTriplet),
+            Some(RepeatRate::QuarterTriplet) => Some(RepeatRate::Eighth),
+            Some(RepeatRate::Eighth) => Some(RepeatRate::EighthTriplet),
+            Some(RepeatRate::EighthTriplet) => Some(RepeatRate::Sixteenth),
+            Some(RepeatRate::Sixteenth) => Some(RepeatRate::SixteenthTriplet),
+            Some(RepeatRate::SixteenthTriplet) => Some(RepeatRate::ThirtySecond),
+            Some(RepeatRate::ThirtySecond) => Some(RepeatRate::ThirtySecondTriplet),
+            Some(RepeatRate::ThirtySecondTriplet) => None,
+        };
+    }
+
+    pub fn toggle_ramp(&mut self) {
+        match self.ramp {
+            Ramp::Off => self.ramp = Ramp::Up,
+            Ramp::Up => self.ramp = Ramp::Down,
+            Ramp::Down => self.ramp = Ramp::Off,
+        }
+    }
+
+    pub fn should_trigger(&self, tick: u64) -> bool {
+        return match self.rate {
+            Some(rate) => tick.is_multiple_of(rate.ticks()),
+            None => false,
+        };
+    }
+
+    pub fn velocity(&self, velocity: u8, count: u32) -> u8 {
+        let step = count.min(RAMP_STEPS) as f32 / RAMP_STEPS as f32;
+
+        let velocity = match self.ramp {
+            Ramp::Off => velocity as f32,
+            Ramp::Up => velocity as f32 * (0.25 + 0.75 * step),
+            Ramp::Down => velocity as f32 * (1.0 - 0.75 * step),
+        };
+
+        return velocity.round().max(1.0) as u8;
+    }
+
+    pub fn label(&self) -> String {
+        let rate = match self.rate {
+            Some(rate) => rate,
+            None => return "".into(),
+        };
+
+        return match self.ramp {
+            Ramp::Off => format!("Repeat {}", rate.label()),
+            Ramp::Up => format!("Repeat {} Ramp Up", rate.label()),
+            Ramp::Down => format!("Repeat {} Ramp Down", rate.label()),
+        };
+    }
+}
@@ -0,0 +1,55 @@
+#[derive(Clone, Copy)]
+pub enum StealPolicy {
+    Oldest,
+    Quietest,
+    Lowest,
+    Highest,
+}
+
+#[derive(Clone, Copy)]
+pub struct Polyphony {
+    pub limit: Option<usize>,
+    pub policy: StealPolicy,
+}
+
+impl Polyphony {
+    pub fn new() -> Self {
+        return Polyphony {
+            limit: None,
+            policy: StealPolicy::Oldest,
+        };
+    }
+
+    pub fn toggle_limit(&mut self) {
+        self.limit = match self.limit {
+            None => Some(4),
+            Some(4) => Some(6),
+            Some(6) => Some(8),
+            Some(8) => Some(16),
+            Some(_) => None,
+        };
+    }
+
+    pub fn toggle_policy(&mut self) {
+        self.policy = match self.policy {
+            StealPolicy::Oldest => StealPolicy::Quietest,
+            StealPolicy::Quietest => StealPolicy::Lowest,
+            StealPolicy::Lowest => StealPolicy::Highest,
+            StealPolicy::Highest => StealPolicy::Oldest,
+        };
+    }
+
+    pub fn label(&self) -> String {
+        let policy = match self.policy {
+            StealPolicy::Oldest => "oldest",
+            StealPolicy::Quietest => "quietest",
+            StealPolicy::Lowest => "lowest",
+            StealPolicy::Highest => "highest",
+        };
+
+        return match self.limit {
+            Some(limit) => format!("{} voices / steal {}", limit, policy),
+            None => "".into(),
+        };
+    }
+}
@@ -0,0 +1,161 @@
+use super::config_lines;
+use crate::events::*;
+use std::collections::HashMap;
+use std::error::Error;
+
+static PADS: [Key; 5] = [Key::Num6, Key::Num7, Key::Num8, Key::Num9, Key::Num0];
+
+static NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProgressionMode {
+    Step,
+    Clock,
+}
+
+pub struct Progressions {
+    pads: HashMap<Key, Vec<i32>>,
+    pub mode: ProgressionMode,
+    active: Option<(Key, usize)>,
+}
+
+impl Progressions {
+    pub fn new() -> Self {
+        let defaults = [
+            vec![0, 5, 2, 6],
+            vec![0, 3, 4, 0],
+            vec![0, 6, 5, 6],
+            vec![1, 4, 0],
+            vec![0, 5, 3, 4],
+        ];
+
+        return Progressions {
+            pads: PADS.iter().cloned().zip(defaults.iter().cloned()).collect(),
+            mode: ProgressionMode::Step,
+            active: None,
+        };
+    }
+
+    // A progression line reads `<key> <degree> <degree> ...`, with degrees
+    // written as roman numerals or numbers, for example `Num6 i VI III VII`.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut pads = HashMap::new();
+
+        for line in config_lines(path)? {
+            let line = line.as_str();
+
+            let (key, degrees) = parse_progression(line)
+                .ok_or_else(|| format!("invalid progression: {}", line))?;
+
+            pads.insert(key, degrees);
+        }
+
+        return Ok(Progressions {
+            pads,
+            mode: ProgressionMode::Step,
+            active: None,
+        });
+    }
+
+    pub fn is_pad(&self, key: &Key) -> bool {
+        return self.pads.contains_key(key);
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            ProgressionMode::Step => ProgressionMode::Clock,
+            ProgressionMode::Clock => ProgressionMode::Step,
+        };
+    }
+
+    pub fn playing(&self) -> Option<Key> {
+        return self.active.as_ref().map(|(key, _)| key.clone());
+    }
+
+    // Moves to the next chord of the progression on `key`, starting over
+    // when another pad was played last, and returns its scale degree.
+    pub fn advance(&mut self, key: &Key) -> Option<i32> {
+        let degrees = self.pads.get(key)?;
+
+        let position = match &self.active {
+            Some((active, position)) if active == key => (position + 1) % degrees.len(),
+            _ => 0,
+        };
+
+        self.active = Some((key.clone(), position));
+
+        return Some(degrees[position]);
+    }
+
+    pub fn stop(&mut self) -> Option<Key> {
+        return self.active.take().map(|(key, _)| key);
+    }
+
+    pub fn pad_label(&self, key: &Key) -> Option<String> {
+        let degrees = self.pads.get(key)?;
+
+        return Some(
+            degrees
+                .iter()
+                .map(|degree| numeral(*degree))
+                .collect::<Vec<String>>()
+                .join("-"),
+        );
+    }
+
+    pub fn label(&self) -> String {
+        let (key, position) = match &self.active {
+            Some(active) => active,
+            None => return "".into(),
+        };
+
+        let mode = match self.mode {
+            ProgressionMode::Step => "Step",
+            ProgressionMode::Clock => "Clock",
+        };
+
+        return self.pads[key]
+            .iter()
+            .enumerate()
+            .fold(String::from(mode), |label, (i, degree)| {
+                if i == *position {
+                    format!("{} [{}]", label, numeral(*degree))
+                } else {
+                    format!("{} {}", label, numeral(*degree))
+                }
+            });
+    }
+}
+
+fn numeral(degree: i32) -> String {
+    return match NUMERALS.get(degree as usize) {
+        Some(numeral) => numeral.to_string(),
+        None => (degree + 1).to_string(),
+    };
+}
+
+pub fn parse_degree(word: &str) -> Option<i32> {
+    if let Ok(degree) = word.parse::<i32>() {
+        return degree.checked_sub(1).filter(|degree| *degree >= 0);
+    }
+
+    return NUMERALS
+        .iter()
+        .position(|numeral| numeral.eq_ignore_ascii_case(word))
+        .map(|degree| degree as i32);
+}
+
+fn parse_progression(line: &str) -> Option<(Key, Vec<i32>)> {
+    let mut words = line.split_whitespace();
+
+    let name = words.next()?;
+    let key = PADS.iter().find(|key| format!("{}", key) == name)?.clone();
+
+    let degrees = words.map(parse_degree).collect::<Option<Vec<i32>>>()?;
+
+    if degrees.is_empty() {
+        return None;
+    }
+
+    return Some((key, degrees));
+}
@@ -0,0 +1,87 @@
+// Deviations are grouped in buckets of this many milliseconds.
+pub static BUCKET_MS: i64 = 10;
+
+static BUCKETS: usize = 11;
+
+// Presses within this many milliseconds of the grid count as tight.
+static TOLERANCE_MS: i64 = 25;
+
+pub struct Rhythm {
+    pub enabled: bool,
+    pub channel: u8,
+    pub note: u8,
+    pub subdivision: u64,
+    histogram: Vec<u32>,
+    presses: u32,
+    tight: u32,
+    total_offset: i64,
+}
+
+impl Rhythm {
+    pub fn new() -> Self {
+        return Rhythm {
+            enabled: false,
+            channel: 9,
+            note: 37,
+            subdivision: 1,
+            histogram: vec![0; BUCKETS],
+            presses: 0,
+            tight: 0,
+            total_offset: 0,
+        };
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+
+        self.histogram = vec![0; BUCKETS];
+        self.presses = 0;
+        self.tight = 0;
+        self.total_offset = 0;
+    }
+
+    pub fn toggle_subdivision(&mut self) {
+        self.subdivision = match self.subdivision {
+            1 => 2,
+            2 => 4,
+            _ => 1,
+        };
+    }
+
+    // Records how far a press landed from the grid, negative when early.
+    pub fn record(&mut self, offset_ms: i64) {
+        let middle = (BUCKETS / 2) as i64;
+        let bucket = (offset_ms + BUCKET_MS / 2).div_euclid(BUCKET_MS) + middle;
+        let bucket = bucket.max(0).min(BUCKETS as i64 - 1);
+
+        self.histogram[bucket as usize] += 1;
+        self.presses += 1;
+        self.total_offset += offset_ms;
+
+        if offset_ms.abs() <= TOLERANCE_MS {
+            self.tight += 1;
+        }
+    }
+
+    // Press counts from the earliest to the latest bucket, centered on the grid.
+    pub fn histogram(&self) -> &Vec<u32> {
+        return &self.histogram;
+    }
+
+    pub fn label(&self) -> String {
+        if !self.enabled {
+            return "".into();
+        }
+
+        if self.presses == 0 {
+            return format!("Rhythm 1/{}", self.subdivision * 4);
+        }
+
+        return format!(
+            "Rhythm 1/{} / {}% tight / avg {:+}ms",
+            self.subdivision * 4,
+            self.tight * 100 / self.presses,
+            self.total_offset / self.presses as i64
+        );
+    }
+}
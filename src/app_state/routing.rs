@@ -0,0 +1,116 @@
+use super::{PlayMode, Row};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy)]
+pub enum RoutingPreset {
+    Single,
+    Rows,
+    ChordLayer,
+    Modes,
+}
+
+pub struct ChannelRouting {
+    pub channel: u8,
+    pub rows: HashMap<Row, u8>,
+    pub modes: HashMap<PlayMode, u8>,
+    pub chord_layer: Option<u8>,
+    preset: RoutingPreset,
+}
+
+impl ChannelRouting {
+    pub fn new() -> Self {
+        return ChannelRouting {
+            channel: 0,
+            rows: HashMap::new(),
+            modes: HashMap::new(),
+            chord_layer: None,
+            preset: RoutingPreset::Single,
+        };
+    }
+
+    pub fn channel_for(&self, row: Option<Row>, play_mode: PlayMode, chord_tone: bool) -> u8 {
+        if chord_tone {
+            if let Some(channel) = self.chord_layer {
+                return channel;
+            }
+        }
+
+        if let Some(channel) = row.and_then(|row| self.rows.get(&row)) {
+            return *channel;
+        }
+
+        if let Some(channel) = self.modes.get(&play_mode) {
+            return *channel;
+        }
+
+        return self.channel;
+    }
+
+    pub fn channels(&self) -> Vec<u8> {
+        let mut channels = vec![self.channel];
+
+        channels.extend(self.rows.values());
+        channels.extend(self.modes.values());
+        channels.extend(self.chord_layer.iter());
+
+        channels.sort();
+        channels.dedup();
+
+        return channels;
+    }
+
+    pub fn increase_channel(&mut self) {
+        self.channel = (self.channel + 1) % 16;
+        self.apply_preset();
+    }
+
+    pub fn decrease_channel(&mut self) {
+        self.channel = (self.channel + 15) % 16;
+        self.apply_preset();
+    }
+
+    pub fn toggle_preset(&mut self) {
+        self.preset = match self.preset {
+            RoutingPreset::Single => RoutingPreset::Rows,
+            RoutingPreset::Rows => RoutingPreset::ChordLayer,
+            RoutingPreset::ChordLayer => RoutingPreset::Modes,
+            RoutingPreset::Modes => RoutingPreset::Single,
+        };
+
+        self.apply_preset();
+    }
+
+    fn apply_preset(&mut self) {
+        let next = (self.channel + 1) % 16;
+
+        self.rows.clear();
+        self.modes.clear();
+        self.chord_layer = None;
+
+        match self.preset {
+            RoutingPreset::Single => {}
+            RoutingPreset::Rows => {
+                self.rows.insert(Row::Upper, next);
+            }
+            RoutingPreset::ChordLayer => {
+                self.chord_layer = Some(next);
+            }
+            RoutingPreset::Modes => {
+                self.modes.insert(PlayMode::Chord, next);
+                self.modes.insert(PlayMode::Chord7, next);
+            }
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let label = format!("Ch {}", self.channel + 1);
+        let next = (self.channel + 1) % 16 + 1;
+
+        return match self.preset {
+            RoutingPreset::Single => label,
+            RoutingPreset::Rows => format!("{} / Upper Ch {}", label, next),
+            RoutingPreset::ChordLayer => format!("{} / Chord Layer Ch {}", label, next),
+            RoutingPreset::Modes => format!("{} / Chords Ch {}", label, next),
+        };
+    }
+}
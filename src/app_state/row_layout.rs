@@ -0,0 +1,46 @@
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Row {
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy)]
+pub enum RowLayout {
+    Fifths,
+    Fourths,
+    Thirds,
+    Sequential,
+    Octave,
+}
+
+impl RowLayout {
+    pub fn offset(&self, scale_length: i32) -> i32 {
+        return match self {
+            RowLayout::Fifths => 4,
+            RowLayout::Fourths => 3,
+            RowLayout::Thirds => 2,
+            RowLayout::Sequential => 9,
+            RowLayout::Octave => scale_length,
+        };
+    }
+
+    pub fn toggle(&mut self) {
+        *self = match self {
+            RowLayout::Fifths => RowLayout::Fourths,
+            RowLayout::Fourths => RowLayout::Thirds,
+            RowLayout::Thirds => RowLayout::Sequential,
+            RowLayout::Sequential => RowLayout::Octave,
+            RowLayout::Octave => RowLayout::Fifths,
+        };
+    }
+
+    pub fn label(&self) -> String {
+        return match self {
+            RowLayout::Fifths => "Rows 5ths".into(),
+            RowLayout::Fourths => "Rows 4ths".into(),
+            RowLayout::Thirds => "Rows 3rds".into(),
+            RowLayout::Sequential => "Rows Sequential".into(),
+            RowLayout::Octave => "Rows Octave".into(),
+        };
+    }
+}
@@ -0,0 +1,198 @@
+use super::config_lines;
+use super::progressions::parse_degree;
+use crate::clock::TICKS_PER_BEAT;
+use crate::events::*;
+use std::error::Error;
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FollowMode {
+    Wait,
+    Clock,
+}
+
+pub struct SongStep {
+    pub positions: Vec<i32>,
+    pub ticks: u64,
+}
+
+pub struct SongFollow {
+    steps: Vec<SongStep>,
+    pub mode: FollowMode,
+    pub active: bool,
+    index: usize,
+    targets: Vec<Key>,
+    step_started: Option<Instant>,
+    hits: u32,
+    misses: u32,
+    delays: Vec<u128>,
+    report: String,
+}
+
+impl SongFollow {
+    pub fn new() -> Self {
+        return SongFollow {
+            steps: Vec::new(),
+            mode: FollowMode::Wait,
+            active: false,
+            index: 0,
+            targets: Vec::new(),
+            step_started: None,
+            hits: 0,
+            misses: 0,
+            delays: Vec::new(),
+            report: String::new(),
+        };
+    }
+
+    // A song is a list of steps separated by whitespace. Each step is a scale
+    // position, several positions joined with `+`, or a roman numeral for a
+    // triad, optionally followed by `:<beats>`, for example `0 2:2 0+2+4 VI:4`.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut song = SongFollow::new();
+
+        for line in config_lines(path)? {
+            let line = line.as_str();
+
+            for word in line.split_whitespace() {
+                let step = parse_step(word).ok_or_else(|| format!("invalid step: {}", word))?;
+                song.steps.push(step);
+            }
+        }
+
+        return Ok(song);
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            FollowMode::Wait => FollowMode::Clock,
+            FollowMode::Clock => FollowMode::Wait,
+        };
+    }
+
+    // Starts from the first step, or stops a song that is being played.
+    // Returns whether a song is now running.
+    pub fn toggle(&mut self) -> bool {
+        if self.active || self.steps.is_empty() {
+            self.active = false;
+            self.targets.clear();
+
+            return false;
+        }
+
+        self.active = true;
+        self.index = 0;
+        self.hits = 0;
+        self.misses = 0;
+        self.delays.clear();
+        self.report.clear();
+
+        return true;
+    }
+
+    pub fn step(&self) -> Option<&SongStep> {
+        if !self.active {
+            return None;
+        }
+
+        return self.steps.get(self.index);
+    }
+
+    pub fn begin_step(&mut self, targets: Vec<Key>) {
+        self.targets = targets;
+        self.step_started = Some(Instant::now());
+    }
+
+    pub fn is_target(&self, key: &Key) -> bool {
+        return self.targets.contains(key);
+    }
+
+    // Scores a key press and returns whether every target of the current
+    // step has now been played.
+    pub fn press(&mut self, key: &Key) -> bool {
+        match self.targets.iter().position(|target| target == key) {
+            Some(index) => {
+                self.targets.remove(index);
+                self.hits += 1;
+
+                if let Some(started) = self.step_started {
+                    self.delays.push(started.elapsed().as_millis());
+                }
+            }
+            None => self.misses += 1,
+        }
+
+        return self.targets.is_empty();
+    }
+
+    // Moves to the next step, counting unplayed targets as misses. Returns
+    // false once the song has ended.
+    pub fn next(&mut self) -> bool {
+        self.misses += self.targets.len() as u32;
+        self.targets.clear();
+        self.index += 1;
+
+        if self.index < self.steps.len() {
+            return true;
+        }
+
+        self.active = false;
+
+        let total = (self.hits + self.misses).max(1);
+        let delay = self.delays.iter().sum::<u128>() / (self.delays.len().max(1) as u128);
+
+        self.report = format!(
+            "Song {}% accurate / {}ms average delay",
+            self.hits * 100 / total,
+            delay
+        );
+
+        return false;
+    }
+
+    pub fn label(&self) -> String {
+        if !self.active {
+            return self.report.clone();
+        }
+
+        let mode = match self.mode {
+            FollowMode::Wait => "wait",
+            FollowMode::Clock => "clock",
+        };
+
+        return format!(
+            "Song {}/{} ({}) / {} hits / {} misses",
+            self.index + 1,
+            self.steps.len(),
+            mode,
+            self.hits,
+            self.misses
+        );
+    }
+}
+
+fn parse_step(word: &str) -> Option<SongStep> {
+    let mut parts = word.split(':');
+    let notes = parts.next()?;
+
+    let beats: f64 = match parts.next() {
+        Some(beats) => beats.parse().ok()?,
+        None => 1.0,
+    };
+
+    let positions = if notes.chars().all(|c| c.is_ascii_alphabetic()) {
+        let degree = parse_degree(notes)?;
+
+        vec![degree, degree + 2, degree + 4]
+    } else {
+        notes
+            .split('+')
+            .map(|position| position.parse().ok().filter(|position| *position >= 0))
+            .collect::<Option<Vec<i32>>>()?
+    };
+
+    return Some(SongStep {
+        positions,
+        ticks: (beats * TICKS_PER_BEAT as f64).round().max(1.0) as u64,
+    });
+}
@@ -0,0 +1,130 @@
+use super::{PlayMode, Row};
+use std::collections::HashMap;
+
+pub struct Zone {
+    pub play_mode: PlayMode,
+    pub channel: u8,
+    pub octave: i32,
+}
+
+pub struct Split {
+    pub enabled: bool,
+    pub zones: HashMap<Row, Zone>,
+}
+
+impl Zone {
+    pub fn increase_octave(&mut self) {
+        self.octave = (self.octave + 1).min(3);
+    }
+
+    pub fn decrease_octave(&mut self) {
+        self.octave = (self.octave - 1).max(-3);
+    }
+
+    pub fn toggle_play_mode(&mut self) {
+        self.play_mode = self.play_mode.next();
+    }
+
+    pub fn label(&self, row: Row) -> String {
+        return format!(
+            "{:?} {} Ch {} Oct {:+}",
+            row,
+            self.play_mode.label(),
+            self.channel + 1,
+            self.octave
+        );
+    }
+}
+
+impl Split {
+    pub fn new() -> Self {
+        return Split::with(Row::Lower, 0, 1);
+    }
+
+    // Parses "<chord row>:<chord channel>:<melody channel>", e.g. "upper:3:1"
+    // to comp on the Q row and solo on the A row.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let parts: Vec<&str> = spec.split(':').collect();
+
+        if parts.len() != 3 {
+            return None;
+        }
+
+        let chord_row = match parts[0].to_lowercase().as_str() {
+            "lower" => Row::Lower,
+            "upper" => Row::Upper,
+            _ => return None,
+        };
+
+        let channel = |part: &str| match part.parse::<u8>() {
+            Ok(channel) if (1..=16).contains(&channel) => Some(channel - 1),
+            _ => None,
+        };
+
+        return Some(Split::with(chord_row, channel(parts[1])?, channel(parts[2])?));
+    }
+
+    fn with(chord_row: Row, chord_channel: u8, melody_channel: u8) -> Self {
+        let melody_row = match chord_row {
+            Row::Lower => Row::Upper,
+            Row::Upper => Row::Lower,
+        };
+
+        let mut zones = HashMap::new();
+
+        zones.insert(
+            chord_row,
+            Zone {
+                play_mode: PlayMode::Chord,
+                channel: chord_channel,
+                octave: -1,
+            },
+        );
+
+        zones.insert(
+            melody_row,
+            Zone {
+                play_mode: PlayMode::Single,
+                channel: melody_channel,
+                octave: 0,
+            },
+        );
+
+        return Split {
+            enabled: false,
+            zones,
+        };
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn zone(&self, row: Option<Row>) -> Option<&Zone> {
+        if !self.enabled {
+            return None;
+        }
+
+        return row.and_then(|row| self.zones.get(&row));
+    }
+
+    pub fn zone_mut(&mut self, row: Row) -> Option<&mut Zone> {
+        return self.zones.get_mut(&row);
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        if !self.enabled {
+            return Vec::new();
+        }
+
+        let mut labels = Vec::new();
+
+        for row in [Row::Upper, Row::Lower].iter() {
+            if let Some(zone) = self.zones.get(row) {
+                labels.push(zone.label(*row));
+            }
+        }
+
+        return labels;
+    }
+}
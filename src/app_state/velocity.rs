@@ -0,0 +1,110 @@
+pub enum VelocityCurve {
+    Linear,
+    Soft,
+    Hard,
+    Fixed,
+}
+
+pub struct Velocity {
+    pub level: u8,
+    pub curve: VelocityCurve,
+    pub accent: bool,
+    pub accent_amount: u8,
+    pub note_off: u8,
+}
+
+impl Velocity {
+    pub fn new() -> Self {
+        return Velocity {
+            level: 80,
+            curve: VelocityCurve::Linear,
+            accent: false,
+            accent_amount: 32,
+            note_off: 64,
+        };
+    }
+
+    pub fn increase(&mut self, nr: u8) {
+        self.level = clamp(self.level as i32 + nr as i32);
+    }
+
+    pub fn decrease(&mut self, nr: u8) {
+        self.level = clamp(self.level as i32 - nr as i32);
+    }
+
+    pub fn set_level(&mut self, amount: f32) {
+        self.level = clamp((64.0 + amount.clamp(0.0, 1.0) * 63.0).round() as i32);
+    }
+
+    pub fn increase_note_off(&mut self, nr: u8) {
+        self.note_off = clamp(self.note_off as i32 + nr as i32);
+    }
+
+    pub fn decrease_note_off(&mut self, nr: u8) {
+        self.note_off = clamp(self.note_off as i32 - nr as i32);
+    }
+
+    pub fn toggle_curve(&mut self) {
+        match self.curve {
+            VelocityCurve::Linear => self.curve = VelocityCurve::Soft,
+            VelocityCurve::Soft => self.curve = VelocityCurve::Hard,
+            VelocityCurve::Hard => self.curve = VelocityCurve::Fixed,
+            VelocityCurve::Fixed => self.curve = VelocityCurve::Linear,
+        }
+    }
+
+    pub fn note_on(&self) -> u8 {
+        let level = self.level as f32 / 127.0;
+
+        let velocity = match self.curve {
+            VelocityCurve::Linear => level,
+            VelocityCurve::Soft => level.sqrt(),
+            VelocityCurve::Hard => level * level,
+            VelocityCurve::Fixed => 1.0,
+        };
+
+        let mut velocity = (velocity * 127.0).round() as i32;
+
+        if self.accent {
+            velocity += self.accent_amount as i32;
+        }
+
+        return clamp(velocity);
+    }
+
+    pub fn curve_label(&self) -> String {
+        return match self.curve {
+            VelocityCurve::Linear => "Linear".into(),
+            VelocityCurve::Soft => "Soft".into(),
+            VelocityCurve::Hard => "Hard".into(),
+            VelocityCurve::Fixed => "Fixed".into(),
+        };
+    }
+
+    pub fn label(&self) -> String {
+        let label = format!(
+            "Vel {} {} / Off {}",
+            self.level,
+            self.curve_label(),
+            self.note_off
+        );
+
+        if self.accent {
+            return format!("{} Accent", label);
+        }
+
+        return label;
+    }
+}
+
+fn clamp(velocity: i32) -> u8 {
+    if velocity < 1 {
+        return 1;
+    }
+
+    if velocity > 127 {
+        return 127;
+    }
+
+    return velocity as u8;
+}
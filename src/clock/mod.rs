@@ -0,0 +1,53 @@
+use crate::app_state::AppState;
+use crate::events::*;
+use std::sync::*;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub static TICKS_PER_BEAT: u64 = 24;
+
+pub struct Clock {
+    handle: thread::JoinHandle<()>,
+}
+
+impl Clock {
+    pub fn new(app_state: Arc<RwLock<AppState>>, event_bus: &mut EventBus) -> Self {
+        let events_recv = event_bus.new_receive();
+        let emitter = event_bus.emitter(Source::Clock);
+
+        let handle = thread::spawn(move || {
+            let mut tick: u64 = 0;
+            let mut next = Instant::now();
+
+            loop {
+                let tempo = app_state.read().unwrap().tempo;
+                let timeout = next.saturating_duration_since(Instant::now());
+
+                select! {
+                    recv(events_recv) -> msg => {
+                        match msg.map(|envelope| envelope.event).unwrap_or_else({ |_| Event::None }) {
+                            Event::Quit { } => {
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                    default(timeout) => {
+                        emitter.send_at(Event::Clock(tick), next).unwrap_or_default();
+
+                        tick += 1;
+                        next += Duration::from_micros(60_000_000 / (tempo as u64 * TICKS_PER_BEAT));
+                    }
+                }
+            }
+        });
+
+        return Clock { handle };
+    }
+
+    pub fn wait(self) {
+        self.handle.join().unwrap_or_else(|_error| {
+            return;
+        });
+    }
+}
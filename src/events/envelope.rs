@@ -0,0 +1,44 @@
+use super::event::Event;
+use crossbeam_channel::{SendError, Sender};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Render,
+    Player,
+    Clock,
+    Input,
+}
+
+// An event together with when and where it was created, as opposed to when
+// the bus got around to delivering it.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    pub event: Event,
+    pub at: Instant,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    sender: Sender<Envelope>,
+    source: Source,
+}
+
+impl Emitter {
+    pub fn new(sender: Sender<Envelope>, source: Source) -> Self {
+        return Emitter { sender, source };
+    }
+
+    pub fn send(&self, event: Event) -> Result<(), SendError<Envelope>> {
+        return self.send_at(event, Instant::now());
+    }
+
+    pub fn send_at(&self, event: Event, at: Instant) -> Result<(), SendError<Envelope>> {
+        return self.sender.send(Envelope {
+            event,
+            at,
+            source: self.source,
+        });
+    }
+}
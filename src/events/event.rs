@@ -6,9 +6,65 @@ pub enum NoteMessage {
     Off = 0x80,
 }
 
+pub static CONTROL_CHANGE: u8 = 0xB0;
+pub static PROGRAM_CHANGE: u8 = 0xC0;
+pub static PITCH_BEND: u8 = 0xE0;
+pub static MODULATION_WHEEL: u8 = 1;
+pub static EXPRESSION: u8 = 11;
+pub static SUSTAIN_PEDAL: u8 = 64;
+pub static PORTAMENTO_TIME: u8 = 5;
+pub static PORTAMENTO: u8 = 65;
+pub static ALL_SOUND_OFF: u8 = 120;
+pub static ALL_NOTES_OFF: u8 = 123;
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Key {
     Space,
+    Tab,
+    Escape,
+    Shift,
+    Alt,
+    Minus,
+    Equals,
+    LeftBracket,
+    RightBracket,
+    Backslash,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Comma,
+    Period,
+    Quote,
+    Backquote,
+    Insert,
+    Delete,
+    Left,
+    Right,
+    Semicolon,
+    Slash,
+    Enter,
+    Backspace,
+    Ctrl,
+    CapsLock,
+    Pause,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Up,
+    Down,
     Num0,
     Num1,
     Num2,
@@ -58,10 +114,34 @@ impl fmt::Display for Key {
 pub enum Event {
     None,
     Note {
+        message: NoteMessage,
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    ControlChange {
+        channel: u8,
+        control: u8,
+        value: u8,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    PitchBend {
+        channel: u8,
+        value: u16,
+    },
+    ExternalNote {
         message: NoteMessage,
         note: u8,
         velocity: u8,
     },
+    Bend(f32),
+    Modulation(f32),
+    Velocity(f32),
+    Expression(f32),
+    Clock(u64),
     KeyDown(Key),
     KeyUp(Key),
     Quit,
@@ -1,19 +1,41 @@
+use super::envelope::{Emitter, Envelope, Source};
 use super::event::Event;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use log::info;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
-#[derive(Debug)]
-struct EventBusInner {
-    local_receiver: Receiver<Event>,
-    all_emitters: Vec<Sender<Event>>,
+// Receivers can attach before or after the bus has started. A receiver
+// detaches by being dropped; its channel is pruned the next time an event
+// fails to reach it. Threads that attach receivers later keep a clone of
+// this handle.
+#[derive(Debug, Clone)]
+pub struct Subscriptions {
+    all_emitters: Arc<Mutex<Vec<Sender<Envelope>>>>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&self) -> Receiver<Envelope> {
+        let (events_emitter, events_recv) = unbounded();
+
+        self.all_emitters.lock().unwrap().push(events_emitter);
+
+        return events_recv;
+    }
+
+    fn publish(&self, envelope: &Envelope) {
+        self.all_emitters
+            .lock()
+            .unwrap()
+            .retain(|emitter| emitter.send(envelope.clone()).is_ok());
+    }
 }
 
 #[derive(Debug)]
 pub struct EventBus {
-    pub emitter: Sender<Event>,
-    inner: Arc<Mutex<EventBusInner>>,
+    sender: Sender<Envelope>,
+    local_receiver: Receiver<Envelope>,
+    subscriptions: Subscriptions,
     handle: Option<thread::JoinHandle<()>>,
 }
 
@@ -22,51 +44,45 @@ impl EventBus {
         let (events_emitter, events_recv) = unbounded();
 
         return EventBus {
-            emitter: events_emitter,
-            inner: Arc::new(Mutex::new(EventBusInner {
-                local_receiver: events_recv,
-                all_emitters: Vec::new(),
-            })),
+            sender: events_emitter,
+            local_receiver: events_recv,
+            subscriptions: Subscriptions {
+                all_emitters: Arc::new(Mutex::new(Vec::new())),
+            },
             handle: None,
         };
     }
 
-    pub fn new_receive(&mut self) -> crossbeam_channel::Receiver<Event> {
-        let (events_emitter, events_recv) = unbounded();
-
-        let mut inner = self.inner.lock().unwrap();
+    pub fn emitter(&self, source: Source) -> Emitter {
+        return Emitter::new(self.sender.clone(), source);
+    }
 
-        inner.all_emitters.push(events_emitter);
+    pub fn new_receive(&self) -> crossbeam_channel::Receiver<Envelope> {
+        return self.subscriptions.subscribe();
+    }
 
-        return events_recv;
+    pub fn subscriptions(&self) -> Subscriptions {
+        return self.subscriptions.clone();
     }
 
     pub fn start(&mut self) {
-        let innert_copy = self.inner.clone();
-
-        let handle = thread::spawn(move || {
-            let inner = &innert_copy.lock().unwrap();
-
-            loop {
-                select! {
-                    recv(inner.local_receiver) -> event => {
-                        let my_event = event.unwrap();
-
-                        info!("EVENT {:?}", my_event);
-
-                        for e in inner.all_emitters.clone() {
-                            match e.send(my_event.clone()) {
-                                Err(_) => {},
-                                _ => {}
-                            }
-                        };
-
-                        match my_event {
-                            Event::Quit => {
-                                break;
-                            }
-                            _ => {}
+        let local_receiver = self.local_receiver.clone();
+        let subscriptions = self.subscriptions.clone();
+
+        let handle = thread::spawn(move || loop {
+            select! {
+                recv(local_receiver) -> event => {
+                    let my_event = event.unwrap();
+
+                    info!("EVENT {:?} from {:?}", my_event.event, my_event.source);
+
+                    subscriptions.publish(&my_event);
+
+                    match my_event.event {
+                        Event::Quit => {
+                            break;
                         }
+                        _ => {}
                     }
                 }
             }
@@ -81,3 +97,33 @@ impl EventBus {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prunes_receivers_dropped_after_start() {
+        let mut event_bus = EventBus::new();
+        let subscriptions = event_bus.subscriptions();
+        let emitter = event_bus.emitter(Source::Input);
+
+        event_bus.start();
+
+        let kept = subscriptions.subscribe();
+        let dropped = subscriptions.subscribe();
+        assert_eq!(subscriptions.all_emitters.lock().unwrap().len(), 2);
+
+        drop(dropped);
+        emitter.send(Event::Quit).unwrap();
+
+        match kept.recv().unwrap().event {
+            Event::Quit => {}
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        event_bus.wait();
+
+        assert_eq!(subscriptions.all_emitters.lock().unwrap().len(), 1);
+    }
+}
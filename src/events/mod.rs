@@ -1,5 +1,10 @@
+pub mod envelope;
 pub mod event;
 pub mod event_bus;
 
-pub use self::event::{Event, Key, NoteMessage};
-pub use self::event_bus::EventBus;
+pub use self::event::{
+    Event, Key, NoteMessage, ALL_NOTES_OFF, ALL_SOUND_OFF, CONTROL_CHANGE, EXPRESSION,
+    MODULATION_WHEEL, PITCH_BEND, PORTAMENTO, PORTAMENTO_TIME, PROGRAM_CHANGE, SUSTAIN_PEDAL,
+};
+pub use self::envelope::{Emitter, Envelope, Source};
+pub use self::event_bus::{EventBus, Subscriptions};
@@ -0,0 +1,92 @@
+use crate::app_state::AppState;
+use crate::events::*;
+use log::info;
+use midir::{Ignore, MidiInput, MidiInputConnection};
+use std::env;
+use std::sync::*;
+
+pub struct Input {
+    connection: Option<MidiInputConnection<()>>,
+}
+
+impl Input {
+    pub fn new(app_state: Arc<RwLock<AppState>>, event_bus: &mut EventBus) -> Input {
+        let emitter = event_bus.emitter(Source::Input);
+
+        let port_filter = match env::var("MIDI_INPUT") {
+            Ok(port_filter) => port_filter,
+            Err(_) => return Input { connection: None },
+        };
+
+        let mut midi_in = MidiInput::new("Midi seq input").unwrap();
+        midi_in.ignore(Ignore::All);
+
+        let port = (0..midi_in.port_count()).find(|port| match midi_in.port_name(*port) {
+            Ok(name) => name.contains(&port_filter),
+            Err(_) => false,
+        });
+
+        let port = match port {
+            Some(port) => port,
+            None => {
+                info!("No MIDI input port matching {}", port_filter);
+                return Input { connection: None };
+            }
+        };
+
+        let port_name = midi_in.port_name(port).unwrap_or_default();
+
+        let connection = midi_in.connect(
+            port,
+            "midi-seq-input",
+            move |_stamp, message, _| {
+                if message.len() < 3 {
+                    return;
+                }
+
+                let event = match (message[0] & 0xF0, message[2]) {
+                    (0x90, 0) | (0x80, _) => Event::ExternalNote {
+                        message: NoteMessage::Off,
+                        note: message[1],
+                        velocity: message[2],
+                    },
+                    (0x90, velocity) => Event::ExternalNote {
+                        message: NoteMessage::On,
+                        note: message[1],
+                        velocity,
+                    },
+                    (0xB0, value) => Event::ControlChange {
+                        channel: message[0] & 0x0F,
+                        control: message[1],
+                        value,
+                    },
+                    _ => return,
+                };
+
+                emitter.send(event).unwrap_or_default();
+            },
+            (),
+        );
+
+        return match connection {
+            Ok(connection) => {
+                info!("Listening to MIDI input {}", port_name);
+                app_state.write().unwrap().midi_input = Some(port_name);
+
+                Input {
+                    connection: Some(connection),
+                }
+            }
+            Err(_) => {
+                info!("Could not connect to MIDI input {}", port_name);
+                Input { connection: None }
+            }
+        };
+    }
+
+    pub fn close(self) {
+        if let Some(connection) = self.connection {
+            connection.close();
+        }
+    }
+}
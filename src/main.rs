@@ -4,23 +4,30 @@ extern crate env_logger;
 extern crate log;
 
 use log::info;
+use std::env;
 use std::error::Error;
 
 mod app_state;
+mod clock;
 mod events;
+mod input;
 mod output;
 mod player;
 mod scale;
 mod ui;
 
 use app_state::*;
+use clock::Clock;
 use events::EventBus;
+use input::Input;
 use output::Output;
 use player::Player;
 use scale::*;
 use std::sync::*;
 use ui::Render;
 
+static IMPROVISER_MODEL: &str = "./assets/improviser.model";
+
 fn main() {
     env_logger::init();
 
@@ -32,12 +39,68 @@ fn main() {
 
 fn run() -> Result<(), Box<Error>> {
     let scale = NaturalMinor::new(60);
-    let app_state = Arc::new(RwLock::new(AppState::new(Box::new(scale))));
+    let mut app_state = AppState::new(Box::new(scale));
+
+    match Bindings::load("./assets/bindings.conf") {
+        Ok(bindings) => app_state.bindings = bindings,
+        Err(err) => info!("Using default bindings: {}", err),
+    }
+
+    match DrumKit::load("./assets/drums.conf") {
+        Ok(drum_kit) => app_state.drum_kit = drum_kit,
+        Err(err) => info!("Using default drum kit: {}", err),
+    }
+
+    match Progressions::load("./assets/progressions.conf") {
+        Ok(progressions) => app_state.progressions = progressions,
+        Err(err) => info!("Using default progressions: {}", err),
+    }
+
+    match SongFollow::load("./assets/song.txt") {
+        Ok(song_follow) => app_state.song_follow = song_follow,
+        Err(err) => info!("No song to follow: {}", err),
+    }
+
+    match MarkovModel::load(IMPROVISER_MODEL) {
+        Ok(model) => app_state.improviser.model = model,
+        Err(err) => info!("Starting with an empty improviser model: {}", err),
+    }
+
+    if let Ok(seed) = env::var("GENERATOR_SEED") {
+        app_state.generator.seed = seed.parse().ok();
+    }
+
+    if let Ok(seed) = env::var("HUMANIZE_SEED") {
+        app_state.humanize.seed = seed.parse().ok();
+    }
+
+    if let Ok(click) = env::var("METRONOME_CLICK") {
+        let mut parts = click.split(':').map(|part| part.parse::<u8>().ok());
+
+        match (parts.next(), parts.next()) {
+            (Some(Some(channel)), Some(Some(note))) if (1..=16).contains(&channel) => {
+                app_state.rhythm.channel = channel - 1;
+                app_state.rhythm.note = note;
+            }
+            _ => info!("Ignoring METRONOME_CLICK, expected <channel>:<note>"),
+        }
+    }
+
+    if let Ok(split) = env::var("SPLIT_ZONES") {
+        match Split::parse(&split) {
+            Some(split) => app_state.split = split,
+            None => info!("Ignoring SPLIT_ZONES, expected <chord row>:<chord channel>:<melody channel>"),
+        }
+    }
+
+    let app_state = Arc::new(RwLock::new(app_state));
     let mut event_bus = EventBus::new();
 
-    let output = Output::new(event_bus.new_receive());
+    let output = Output::new(app_state.clone(), event_bus.subscriptions());
     let render = Render::new(app_state.clone(), &mut event_bus);
     let player = Player::new(app_state.clone(), &mut event_bus);
+    let clock = Clock::new(app_state.clone(), &mut event_bus);
+    let input = Input::new(app_state.clone(), &mut event_bus);
 
     event_bus.start();
 
@@ -45,9 +108,20 @@ fn run() -> Result<(), Box<Error>> {
 
     info!("Stopping");
 
+    input.close();
+
     output.wait();
     event_bus.wait();
     player.wait();
+    clock.wait();
+
+    let app_state = app_state.read().unwrap();
+
+    if app_state.improviser.model.notes() > 0 {
+        if let Err(err) = app_state.improviser.model.save(IMPROVISER_MODEL) {
+            info!("Could not save the improviser model: {}", err);
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,100 @@
+use crate::events::NoteMessage;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+pub struct Humanizer {
+    rng: StdRng,
+}
+
+impl Humanizer {
+    pub fn new(seed: u64) -> Self {
+        return Humanizer {
+            rng: StdRng::seed_from_u64(seed),
+        };
+    }
+
+    pub fn velocity(&mut self, velocity: u8, range: u8) -> u8 {
+        let range = range as i32;
+        let velocity = velocity as i32 + self.rng.gen_range(-range, range + 1);
+
+        if velocity < 1 {
+            return 1;
+        }
+
+        if velocity > 127 {
+            return 127;
+        }
+
+        return velocity as u8;
+    }
+
+    pub fn offset(&mut self, range: u64) -> Duration {
+        return Duration::from_millis(self.rng.gen_range(0, range + 1));
+    }
+}
+
+// Moves a note-on back so it can't go out before a pending note-off of the
+// same note, which would otherwise cut the new note short.
+pub fn after_pending_off(
+    at: Instant,
+    pending: &[(Instant, Vec<u8>)],
+    channel: u8,
+    note: u8,
+) -> Instant {
+    let off = NoteMessage::Off as u8 | channel;
+
+    return pending
+        .iter()
+        .filter(|(_, message)| message.len() == 3 && message[0] == off && message[1] == note)
+        .map(|(when, _)| *when)
+        .fold(at, |at, when| at.max(when));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_offsets() {
+        let mut first = Humanizer::new(7);
+        let mut second = Humanizer::new(7);
+
+        for _ in 0..100 {
+            assert_eq!(first.offset(20), second.offset(20));
+            assert_eq!(first.velocity(100, 12), second.velocity(100, 12));
+        }
+    }
+
+    #[test]
+    fn note_on_waits_for_pending_off() {
+        let now = Instant::now();
+        let off = now + Duration::from_millis(30);
+        let pending = vec![(off, vec![NoteMessage::Off as u8, 60, 0])];
+
+        assert_eq!(after_pending_off(now, &pending, 0, 60), off);
+        assert_eq!(after_pending_off(now, &pending, 0, 62), now);
+        assert_eq!(after_pending_off(now, &pending, 1, 60), now);
+
+        let later = now + Duration::from_millis(40);
+        assert_eq!(after_pending_off(later, &pending, 0, 60), later);
+    }
+
+    #[test]
+    fn jitter_stays_within_range() {
+        let mut humanizer = Humanizer::new(7);
+
+        for _ in 0..1000 {
+            assert!(humanizer.offset(20) <= Duration::from_millis(20));
+
+            let velocity = humanizer.velocity(100, 12);
+            assert!((88..=112).contains(&velocity));
+
+            let velocity = humanizer.velocity(5, 12);
+            assert!((1..=17).contains(&velocity));
+
+            let velocity = humanizer.velocity(125, 12);
+            assert!((113..=127).contains(&velocity));
+        }
+    }
+}
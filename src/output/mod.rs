@@ -0,0 +1,171 @@
+mod humanize;
+mod voices;
+
+use crate::app_state::AppState;
+use crate::events::{
+    Event, NoteMessage, Subscriptions, ALL_NOTES_OFF, CONTROL_CHANGE, PITCH_BEND, PROGRAM_CHANGE,
+};
+use midir::MidiOutput;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::*;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use self::humanize::{after_pending_off, Humanizer};
+use self::voices::Voices;
+
+pub struct Output {
+    handle: thread::JoinHandle<()>,
+}
+
+impl Output {
+    pub fn new(
+        app_state: Arc<RwLock<AppState>>,
+        subscriptions: Subscriptions,
+    ) -> Output {
+        let out_port = Output::get_port().unwrap();
+        let events_recv = subscriptions.subscribe();
+        let seed = app_state
+            .read()
+            .unwrap()
+            .humanize
+            .seed
+            .unwrap_or_else(rand::random);
+
+        let handle = thread::spawn(move || {
+            let midi_out = MidiOutput::new("Midi seq").unwrap();
+            let mut conn_out = midi_out.connect(out_port, "midi-seq").unwrap();
+
+            let mut humanizer = Humanizer::new(seed);
+            let mut offsets: HashMap<(u8, u8), Duration> = HashMap::new();
+            let mut pending: Vec<(Instant, Vec<u8>)> = Vec::new();
+            let mut voices = Voices::new();
+
+            loop {
+                let timeout = match pending.iter().map(|(at, _)| *at).min() {
+                    Some(at) => at.saturating_duration_since(Instant::now()),
+                    None => Duration::from_secs(1),
+                };
+
+                select! {
+                    recv(events_recv) -> msg => {
+                        match msg.map(|envelope| envelope.event).unwrap_or_else({|_| Event::None }) {
+                            Event::Note {
+                                message,
+                                channel,
+                                note,
+                                velocity,
+                            } => {
+                                let (humanize, polyphony) = {
+                                    let app_state = app_state.read().unwrap();
+                                    (app_state.humanize, app_state.polyphony)
+                                };
+
+                                let now = Instant::now();
+
+                                let (at, velocity) = match message {
+                                    NoteMessage::On if humanize.enabled => {
+                                        let delay = humanizer.offset(humanize.timing_range);
+
+                                        (now + delay, humanizer.velocity(velocity, humanize.velocity_range))
+                                    },
+                                    NoteMessage::On => (now, velocity),
+                                    NoteMessage::Off => {
+                                        (now + offsets.remove(&(channel, note)).unwrap_or_default(), velocity)
+                                    },
+                                };
+
+                                let (at, sounding) = match message {
+                                    NoteMessage::On => {
+                                        let at = after_pending_off(at, &pending, channel, note);
+                                        offsets.insert((channel, note), at - now);
+
+                                        let stolen = voices.note_on(channel, note, velocity, polyphony.limit, polyphony.policy);
+
+                                        for voice in stolen {
+                                            let delay = offsets.remove(&(voice.channel, voice.note)).unwrap_or_default();
+                                            pending.push((now + delay, vec![NoteMessage::Off as u8 | voice.channel, voice.note, 0]));
+                                        }
+
+                                        (at, true)
+                                    },
+                                    NoteMessage::Off => (at, voices.note_off(channel, note)),
+                                };
+
+                                if sounding {
+                                    pending.push((at, vec![message as u8 | channel, note, velocity]));
+                                }
+                            },
+                            Event::ControlChange { channel, control, value } => {
+                                if control == ALL_NOTES_OFF {
+                                    let note_on = NoteMessage::On as u8 | channel;
+
+                                    pending.retain(|(_, message)| message[0] != note_on);
+                                    voices.clear_channel(channel);
+                                    offsets.retain(|(offset_channel, _), _| *offset_channel != channel);
+                                }
+
+                                pending.push((Instant::now(), vec![CONTROL_CHANGE | channel, control, value]));
+                            },
+                            Event::ProgramChange { channel, program } => {
+                                pending.push((Instant::now(), vec![PROGRAM_CHANGE | channel, program]));
+                            },
+                            Event::PitchBend { channel, value } => {
+                                let lsb = (value & 0x7F) as u8;
+                                let msb = ((value >> 7) & 0x7F) as u8;
+
+                                pending.push((Instant::now(), vec![PITCH_BEND | channel, lsb, msb]));
+                            },
+                            Event::Quit { } => {
+                                for (_, message) in pending.drain(..) {
+                                    conn_out.send(&message).unwrap();
+                                }
+
+                                for channel in 0..16 {
+                                    conn_out.send(&[CONTROL_CHANGE | channel, ALL_NOTES_OFF, 0]).unwrap();
+                                }
+
+                                voices.clear();
+
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                    default(timeout) => {}
+                }
+
+                pending.sort_by_key(|(at, _)| *at);
+
+                let now = Instant::now();
+                let due = pending.iter().take_while(|(at, _)| *at <= now).count();
+
+                for (_, message) in pending.drain(..due) {
+                    conn_out.send(&message).unwrap();
+                }
+            }
+
+            conn_out.close();
+        });
+
+        return Output { handle };
+    }
+
+    fn get_port() -> Result<usize, Box<Error>> {
+        let midi_out = MidiOutput::new("My Test Output").unwrap();
+
+        let out_port = match midi_out.port_count() {
+            0 => return Err("no output port found".into()),
+            _ => 0,
+        };
+
+        return Ok(out_port);
+    }
+
+    pub fn wait(self) {
+        self.handle.join().unwrap_or_else(|_error| {
+            return;
+        });
+    }
+}
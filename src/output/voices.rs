@@ -0,0 +1,135 @@
+use crate::app_state::StealPolicy;
+
+pub struct Voice {
+    pub channel: u8,
+    pub note: u8,
+    velocity: u8,
+}
+
+pub struct Voices {
+    sounding: Vec<Voice>,
+}
+
+impl Voices {
+    pub fn new() -> Self {
+        return Voices {
+            sounding: Vec::new(),
+        };
+    }
+
+    // Registers a new voice and returns the voices that have to be released
+    // to stay within `limit`, chosen according to `policy`.
+    pub fn note_on(
+        &mut self,
+        channel: u8,
+        note: u8,
+        velocity: u8,
+        limit: Option<usize>,
+        policy: StealPolicy,
+    ) -> Vec<Voice> {
+        self.sounding
+            .retain(|voice| voice.channel != channel || voice.note != note);
+
+        let mut stolen = Vec::new();
+
+        if let Some(limit) = limit {
+            while !self.sounding.is_empty() && self.sounding.len() >= limit.max(1) {
+                let index = self.victim(policy);
+                stolen.push(self.sounding.remove(index));
+            }
+        }
+
+        self.sounding.push(Voice {
+            channel,
+            note,
+            velocity,
+        });
+
+        return stolen;
+    }
+
+    // Returns false when the voice is no longer sounding, e.g. because it
+    // was stolen and its note-off has already been sent.
+    pub fn note_off(&mut self, channel: u8, note: u8) -> bool {
+        let count = self.sounding.len();
+
+        self.sounding
+            .retain(|voice| voice.channel != channel || voice.note != note);
+
+        return self.sounding.len() != count;
+    }
+
+    pub fn clear_channel(&mut self, channel: u8) {
+        self.sounding.retain(|voice| voice.channel != channel);
+    }
+
+    pub fn clear(&mut self) {
+        self.sounding.clear();
+    }
+
+    fn victim(&self, policy: StealPolicy) -> usize {
+        let voices = self.sounding.iter().enumerate();
+
+        let victim = match policy {
+            StealPolicy::Oldest => Some(0),
+            StealPolicy::Quietest => voices.min_by_key(|(_, voice)| voice.velocity).map(|(i, _)| i),
+            StealPolicy::Lowest => voices.min_by_key(|(_, voice)| voice.note).map(|(i, _)| i),
+            StealPolicy::Highest => voices.max_by_key(|(_, voice)| voice.note).map(|(i, _)| i),
+        };
+
+        return victim.unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(voices: &[Voice]) -> Vec<u8> {
+        return voices.iter().map(|voice| voice.note).collect();
+    }
+
+    fn steal(policy: StealPolicy) -> Vec<u8> {
+        let mut voices = Voices::new();
+        voices.note_on(0, 64, 100, Some(3), policy);
+        voices.note_on(0, 60, 40, Some(3), policy);
+        voices.note_on(0, 67, 80, Some(3), policy);
+
+        return notes(&voices.note_on(0, 62, 90, Some(3), policy));
+    }
+
+    #[test]
+    fn steals_according_to_policy() {
+        assert_eq!(steal(StealPolicy::Oldest), vec![64]);
+        assert_eq!(steal(StealPolicy::Quietest), vec![60]);
+        assert_eq!(steal(StealPolicy::Lowest), vec![60]);
+        assert_eq!(steal(StealPolicy::Highest), vec![67]);
+    }
+
+    #[test]
+    fn stays_within_limit() {
+        let mut voices = Voices::new();
+
+        assert!(voices.note_on(0, 60, 100, None, StealPolicy::Oldest).is_empty());
+        assert!(voices.note_on(1, 60, 100, None, StealPolicy::Oldest).is_empty());
+        assert!(voices.note_on(0, 62, 100, None, StealPolicy::Oldest).is_empty());
+
+        let stolen = voices.note_on(0, 64, 100, Some(2), StealPolicy::Oldest);
+        assert_eq!(notes(&stolen), vec![60, 60]);
+        assert_eq!(stolen[0].channel, 0);
+        assert_eq!(stolen[1].channel, 1);
+
+        assert!(voices.note_on(0, 64, 100, Some(2), StealPolicy::Oldest).is_empty());
+        assert!(!voices.note_off(0, 60));
+        assert!(voices.note_off(0, 62));
+    }
+
+    #[test]
+    fn retrigger_does_not_steal() {
+        let mut voices = Voices::new();
+        voices.note_on(0, 60, 100, Some(1), StealPolicy::Oldest);
+
+        assert!(voices.note_on(0, 60, 100, Some(1), StealPolicy::Oldest).is_empty());
+        assert_eq!(notes(&voices.note_on(0, 62, 100, Some(1), StealPolicy::Oldest)), vec![60]);
+    }
+}
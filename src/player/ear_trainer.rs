@@ -0,0 +1,93 @@
+use crate::app_state::{EarTraining, Exercise, QUALITIES};
+use crate::clock::TICKS_PER_BEAT;
+use crate::scale::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+
+pub struct EarTrainer {
+    rng: StdRng,
+    prompt: Vec<Vec<u32>>,
+    steps: VecDeque<Vec<u32>>,
+    due: u64,
+    ask_at: u64,
+}
+
+impl EarTrainer {
+    pub fn new(seed: u64) -> Self {
+        return EarTrainer {
+            rng: StdRng::seed_from_u64(seed),
+            prompt: Vec::new(),
+            steps: VecDeque::new(),
+            due: 0,
+            ask_at: 0,
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.prompt.clear();
+        self.steps.clear();
+        self.ask_at = 0;
+    }
+
+    pub fn ready(&self, tick: u64) -> bool {
+        return self.steps.is_empty() && tick >= self.ask_at;
+    }
+
+    // Picks a question at the current level and returns its answer.
+    pub fn ask(
+        &mut self,
+        training: &EarTraining,
+        scale: &Box<dyn Scale + Sync + Send>,
+        tick: u64,
+    ) -> Option<i32> {
+        let answer = *training
+            .choices(scale.notes().len() as i32)
+            .choose(&mut self.rng)?;
+
+        let root = scale.note(0);
+
+        self.prompt = match training.exercise? {
+            Exercise::Degree => vec![chord::get(scale, root), vec![scale.note(answer)]],
+            Exercise::Interval => {
+                let target = scale.note(answer);
+
+                vec![vec![root], vec![target], vec![root, target]]
+            }
+            Exercise::Chord => {
+                let degree = self.rng.gen_range(0, scale.notes().len() as i32);
+                let root = scale.note(degree);
+
+                vec![QUALITIES[answer as usize].1.iter().map(|step| root + step).collect()]
+            }
+        };
+
+        self.replay(tick);
+
+        return Some(answer);
+    }
+
+    pub fn replay(&mut self, tick: u64) {
+        self.steps = self.prompt.iter().cloned().collect();
+        self.steps.push_back(Vec::new());
+        self.due = tick;
+    }
+
+    pub fn schedule(&mut self, tick: u64) {
+        self.ask_at = tick;
+    }
+
+    // Returns the notes of the next prompt step once it is due, or an empty
+    // list when the prompt has finished and its last notes should stop.
+    pub fn advance(&mut self, tick: u64) -> Option<Vec<u32>> {
+        if tick < self.due {
+            return None;
+        }
+
+        let notes = self.steps.pop_front()?;
+        self.due = tick + TICKS_PER_BEAT;
+
+        return Some(notes);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::app_state::Generator;
+use rand::distributions::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+pub enum Step {
+    Hold,
+    Rest,
+    Note(i32),
+}
+
+pub struct MelodyWalker {
+    rng: StdRng,
+    position: Option<i32>,
+}
+
+impl MelodyWalker {
+    pub fn new(seed: u64) -> Self {
+        return MelodyWalker {
+            rng: StdRng::seed_from_u64(seed),
+            position: None,
+        };
+    }
+
+    pub fn restart(&mut self) {
+        self.position = None;
+    }
+
+    // Decides what happens on the next step: keep the current note, rest,
+    // or walk to a new scale position inside the generator's register.
+    pub fn step(&mut self, generator: &Generator, scale_length: i32) -> Step {
+        if !self.rng.gen_bool(generator.density) {
+            return Step::Hold;
+        }
+
+        if self.rng.gen_bool(generator.rest_probability) {
+            return Step::Rest;
+        }
+
+        let (low, high) = generator.register(scale_length);
+
+        let position = match self.position {
+            Some(position) => {
+                let size = match WeightedIndex::new(generator.steps.weights()) {
+                    Ok(distribution) => self.rng.sample(distribution) as i32,
+                    Err(_) => 1,
+                };
+
+                let size = if self.rng.gen_bool(0.5) { size } else { -size };
+
+                if position + size < low || position + size > high {
+                    position - size
+                } else {
+                    position + size
+                }
+            }
+            None => self.rng.gen_range(low, high + 1),
+        };
+
+        let position = position.max(low).min(high);
+        self.position = Some(position);
+
+        return Step::Note(position);
+    }
+}
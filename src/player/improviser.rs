@@ -0,0 +1,126 @@
+use crate::app_state::{MarkovModel, Token, MAX_ORDER};
+use crate::clock::TICKS_PER_BEAT;
+use super::generator::Step;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+static SIXTEENTH: u64 = TICKS_PER_BEAT / 4;
+
+// How long the player has to pause before the improviser answers.
+static IDLE_TICKS: u64 = TICKS_PER_BEAT * 2;
+
+// Pauses longer than this start a new phrase instead of a transition.
+static PHRASE_GAP: u64 = TICKS_PER_BEAT * 4;
+
+static PHRASE_NOTES: u32 = 16;
+
+pub struct Improvisation {
+    rng: StdRng,
+    context: Vec<Token>,
+    pending: Option<(i32, u64)>,
+    next_onset: Option<u64>,
+    remaining: u32,
+}
+
+fn quantize(ticks: u64) -> u64 {
+    let ticks = (ticks + SIXTEENTH / 2) / SIXTEENTH * SIXTEENTH;
+
+    return ticks.max(SIXTEENTH).min(TICKS_PER_BEAT * 4);
+}
+
+impl Improvisation {
+    pub fn new(seed: u64) -> Self {
+        return Improvisation {
+            rng: StdRng::seed_from_u64(seed),
+            context: Vec::new(),
+            pending: None,
+            next_onset: None,
+            remaining: 0,
+        };
+    }
+
+    pub fn is_playing(&self) -> bool {
+        return self.next_onset.is_some();
+    }
+
+    pub fn stop(&mut self) {
+        self.context.clear();
+        self.pending = None;
+        self.next_onset = None;
+    }
+
+    // Learns from a note the player just started at `tick`. The previous
+    // note only becomes a token now that its duration is known.
+    pub fn record(&mut self, degree: i32, tick: u64, model: &mut MarkovModel) {
+        if self.is_playing() {
+            self.stop();
+        }
+
+        // Only the first note of a chord is learned.
+        if let Some((_, onset)) = self.pending {
+            if onset == tick {
+                return;
+            }
+        }
+
+        match self.pending {
+            Some((previous, onset)) if tick - onset <= PHRASE_GAP => {
+                let token = (previous, quantize(tick - onset));
+
+                model.train(&self.context, token);
+                self.push(token);
+            }
+            _ => self.context.clear(),
+        }
+
+        self.pending = Some((degree, tick));
+    }
+
+    // Continues the player's last phrase once they have been idle for a
+    // while, one sampled token at a time.
+    pub fn advance(&mut self, tick: u64, model: &MarkovModel, order: usize) -> Step {
+        if !self.is_playing() {
+            match self.pending {
+                Some((degree, onset)) if tick - onset >= IDLE_TICKS => {
+                    self.pending = None;
+                    self.push((degree, TICKS_PER_BEAT));
+                    self.remaining = PHRASE_NOTES;
+                    self.next_onset = Some(tick);
+                }
+                _ => return Step::Hold,
+            }
+        }
+
+        match self.next_onset {
+            Some(onset) if tick >= onset => {}
+            _ => return Step::Hold,
+        }
+
+        if self.remaining == 0 {
+            self.stop();
+            return Step::Rest;
+        }
+
+        return match model.sample(&self.context, order, &mut self.rng) {
+            Some(token) => {
+                self.remaining -= 1;
+                self.next_onset = Some(tick + token.1);
+                self.push(token);
+
+                Step::Note(token.0)
+            }
+            None => {
+                self.stop();
+                Step::Rest
+            }
+        };
+    }
+
+    fn push(&mut self, token: Token) {
+        self.context.push(token);
+
+        if self.context.len() > MAX_ORDER {
+            self.context.remove(0);
+        }
+    }
+}
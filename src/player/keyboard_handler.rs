@@ -1,23 +1,49 @@
 use crate::app_state::*;
+use crate::clock::TICKS_PER_BEAT;
 use crate::events::*;
 use crate::scale::*;
-use crossbeam_channel::Sender;
+use super::ear_trainer::EarTrainer;
+use super::generator::{MelodyWalker, Step};
+use super::improviser::Improvisation;
+use super::ledger::{NoteLedger, NoteSource};
+use super::mono::MonoVoice;
+use log::info;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::*;
+use std::time::Instant;
 
 static NOTES: [&str; 12] = [
     "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
 ];
 
+// Names a MIDI note the way the key labels do, falling back to the number
+// outside the piano range.
+pub fn midi_to_note(note: u32) -> Cow<'static, str> {
+    if !(21..108).contains(&note) {
+        return Cow::from(note.to_string());
+    }
+
+    return Cow::from(NOTES[note as usize % 12]);
+}
+
 pub struct KeyboardHandler {
-    pub emitter: Sender<Event>,
+    pub emitter: Emitter,
     pub app_state: Arc<RwLock<AppState>>,
     pub mappings: HashMap<u32, Box<str>>,
+    repeats: HashMap<Key, u32>,
+    ledger: NoteLedger,
+    mono: MonoVoice,
+    walker: MelodyWalker,
+    improvisation: Improvisation,
+    trainer: EarTrainer,
+    tick: u64,
+    song_due: u64,
+    last_grid: Option<Instant>,
 }
 
 impl KeyboardHandler {
-    pub fn new(emitter: Sender<Event>, app_state: Arc<RwLock<AppState>>) -> Self {
+    pub fn new(emitter: Emitter, app_state: Arc<RwLock<AppState>>) -> Self {
         let mut index = 9;
         let mut mappings = HashMap::new();
 
@@ -30,34 +56,133 @@ impl KeyboardHandler {
             }
         }
 
+        let seed = app_state
+            .read()
+            .unwrap()
+            .generator
+            .seed
+            .unwrap_or_else(rand::random);
+
         return KeyboardHandler {
             emitter,
             app_state,
             mappings,
+            repeats: HashMap::new(),
+            ledger: NoteLedger::new(),
+            mono: MonoVoice::new(),
+            walker: MelodyWalker::new(seed),
+            improvisation: Improvisation::new(seed),
+            trainer: EarTrainer::new(seed),
+            tick: 0,
+            song_due: 0,
+            last_grid: None,
         };
     }
 
-    pub fn reset(&self) {
-        let app_state = self.app_state.read().unwrap();
+    pub fn reset(&mut self) {
+        let latched_keys = self.app_state.read().unwrap().latched_keys.clone();
 
-        for (key, val) in app_state.pressed_keys.iter() {
-            if *val == true {
-                let notes: Vec<u32> = self.key_to_midi_notes(key.clone());
-
-                for note in notes.iter() {
-                    self.emitter
-                        .send(Event::Note {
-                            message: NoteMessage::Off,
-                            note: *note as u8,
-                            velocity: 10,
-                        })
-                        .unwrap_or_default();
+        for source in self.ledger.sources() {
+            match &source {
+                NoteSource::Key(key) if !latched_keys.contains(key) => {
+                    let notes = self.ledger.remove(&source);
+                    self.send_notes_off(&notes);
                 }
+                _ => {}
             }
         }
+
+        self.mono.clear();
+    }
+
+    pub fn panic(&mut self) {
+        self.ledger.drain();
+        self.mono.clear();
+        self.app_state.write().unwrap().progressions.stop();
+
+        for channel in 0..16 {
+            for control in &[SUSTAIN_PEDAL, ALL_SOUND_OFF, ALL_NOTES_OFF] {
+                self.emitter
+                    .send(Event::ControlChange {
+                        channel,
+                        control: *control,
+                        value: 0,
+                    })
+                    .unwrap_or_default();
+            }
+        }
+
+        let mut app_state = self.app_state.write().unwrap();
+        app_state.latched_keys.clear();
+        app_state.sustained_notes.clear();
+    }
+
+    pub fn set_sustain(&self, sustain: bool) {
+        let channels = self.app_state.read().unwrap().active_channels();
+
+        for channel in channels {
+            self.emitter
+                .send(Event::ControlChange {
+                    channel,
+                    control: SUSTAIN_PEDAL,
+                    value: if sustain { 127 } else { 0 },
+                })
+                .unwrap_or_default();
+        }
+
+        let mut app_state = self.app_state.write().unwrap();
+        app_state.sustain = sustain;
+
+        if !sustain {
+            app_state.sustained_notes.clear();
+        }
+    }
+
+    pub fn set_bend(&self, amount: f32) {
+        let amount = amount.clamp(-1.0, 1.0);
+        let value = (8192.0 + amount * 8191.0).round() as u16;
+        let channels = self.app_state.read().unwrap().active_channels();
+
+        for channel in channels {
+            self.emitter
+                .send(Event::PitchBend { channel, value })
+                .unwrap_or_default();
+        }
+    }
+
+    pub fn set_modulation(&self, amount: f32) {
+        self.send_control(MODULATION_WHEEL, amount);
+    }
+
+    pub fn set_expression(&self, amount: f32) {
+        self.send_control(EXPRESSION, amount);
+    }
+
+    fn send_control(&self, control: u8, amount: f32) {
+        let value = (amount.clamp(0.0, 1.0) * 127.0).round() as u8;
+        let channels = self.app_state.read().unwrap().active_channels();
+
+        for channel in channels {
+            self.emitter
+                .send(Event::ControlChange {
+                    channel,
+                    control,
+                    value,
+                })
+                .unwrap_or_default();
+        }
     }
 
     pub fn key_to_note<'a>(&self, key: Key) -> Cow<str> {
+        if self.is_drum_pad(&key) {
+            let app_state = self.app_state.read().unwrap();
+
+            return match app_state.drum_kit.label(&key) {
+                Some(name) => Cow::from(name),
+                None => Cow::from(""),
+            };
+        }
+
         let label = format!("{:?}", key);
         let midi_note = self.key_to_midi(key);
 
@@ -71,85 +196,784 @@ impl KeyboardHandler {
         };
     }
 
-    pub fn handle_key_on(&self, key: Key) {
-        let notes: Vec<u32> = self.key_to_midi_notes(key);
+    pub fn set_portamento(&self) {
+        let (channels, portamento) = {
+            let app_state = self.app_state.read().unwrap();
+            (app_state.active_channels(), app_state.mono.portamento)
+        };
+
+        for channel in channels {
+            if let Some(time) = portamento {
+                self.emitter
+                    .send(Event::ControlChange {
+                        channel,
+                        control: PORTAMENTO_TIME,
+                        value: time,
+                    })
+                    .unwrap_or_default();
+            }
 
-        for note in notes.iter() {
+            self.emitter
+                .send(Event::ControlChange {
+                    channel,
+                    control: PORTAMENTO,
+                    value: if portamento.is_some() { 127 } else { 0 },
+                })
+                .unwrap_or_default();
+        }
+    }
+
+    pub fn handle_key_on(&mut self, key: Key) {
+        self.follow(&key);
+        self.answer(&key);
+
+        let row = key_position(&key).map(|(row, _)| row);
+        let (mono, priority) = {
+            let app_state = self.app_state.read().unwrap();
+            (app_state.play_mode_for(row) == PlayMode::Mono, app_state.mono.priority)
+        };
+
+        if mono {
+            if let Some(note) = self.key_to_midi_notes(key.clone()).first() {
+                let owner = self.mono.sounding();
+                let (on, off) = self.mono.press(key, *note, priority);
+                self.send_mono(owner, on, off);
+            }
+
+            return;
+        }
+
+        let source = NoteSource::Key(key.clone());
+        let previous = self.ledger.remove(&source);
+        self.send_notes_off(&previous);
+
+        let notes: Vec<(u8, u32)> = self.key_to_midi_notes(key);
+        self.send_notes_on(&notes, self.note_on_velocity());
+        self.ledger.insert(source, notes);
+    }
+
+    pub fn handle_key_off(&mut self, key: Key) {
+        let priority = self.app_state.read().unwrap().mono.priority;
+        let owner = self.mono.sounding();
+        let (on, off) = self.mono.release(&key, priority);
+        self.send_mono(owner, on, off);
+
+        let notes = self.ledger.remove(&NoteSource::Key(key));
+        self.send_notes_off(&notes);
+    }
+
+    pub fn handle_external(&mut self, message: NoteMessage, note: u8, velocity: u8) {
+        let source = NoteSource::External(note);
+        let previous = self.ledger.remove(&source);
+        self.send_notes_off(&previous);
+
+        if let NoteMessage::Off = message {
+            return;
+        }
+
+        let mapped = {
+            let app_state = self.app_state.read().unwrap();
+            app_state.input_mapping.map(&app_state.scale, note)
+        };
+
+        if let Some(mapped) = mapped {
+            let notes = self.midi_notes(None, mapped);
+            self.send_notes_on(&notes, velocity);
+            self.ledger.insert(source, notes);
+        }
+    }
+
+    pub fn handle_latch(&mut self, key: Key) {
+        let (latched, exclusive) = {
+            let app_state = self.app_state.read().unwrap();
+            (
+                app_state.latched_keys.contains(&key),
+                app_state.exclusive_latch(),
+            )
+        };
+
+        if latched {
+            self.app_state.write().unwrap().latched_keys.remove(&key);
+            self.handle_key_off(key);
+            return;
+        }
+
+        if exclusive {
+            self.release_latched();
+        }
+
+        self.handle_key_on(key.clone());
+        self.app_state.write().unwrap().latched_keys.insert(key);
+    }
+
+    pub fn release_latched(&mut self) {
+        let latched: Vec<Key> = self
+            .app_state
+            .write()
+            .unwrap()
+            .latched_keys
+            .drain()
+            .collect();
+
+        for key in latched {
+            self.handle_key_off(key);
+        }
+    }
+
+    pub fn handle_progression(&mut self, key: Key) {
+        let (mode, playing) = {
+            let app_state = self.app_state.read().unwrap();
+            (app_state.progressions.mode, app_state.progressions.playing())
+        };
+
+        if mode == ProgressionMode::Clock && playing.as_ref() == Some(&key) {
+            self.stop_progression();
+            return;
+        }
+
+        if let Some(playing) = playing {
+            if playing != key {
+                self.release_progression(playing);
+            }
+        }
+
+        self.advance_progression(key);
+    }
+
+    pub fn release_progression(&mut self, key: Key) {
+        let notes = self.ledger.remove(&NoteSource::Key(key));
+        self.send_notes_off(&notes);
+    }
+
+    pub fn stop_progression(&mut self) {
+        let playing = self.app_state.write().unwrap().progressions.stop();
+
+        if let Some(key) = playing {
+            self.release_progression(key);
+        }
+    }
+
+    fn advance_progression(&mut self, key: Key) {
+        let degree = match self.app_state.write().unwrap().progressions.advance(&key) {
+            Some(degree) => degree,
+            None => return,
+        };
+
+        let source = NoteSource::Key(key);
+        let previous = self.ledger.remove(&source);
+        let notes = self.progression_chord(degree);
+
+        self.send_notes_off(&previous);
+        self.send_notes_on(&notes, self.note_on_velocity());
+        self.ledger.insert(source, notes);
+    }
+
+    fn progression_chord(&self, degree: i32) -> Vec<(u8, u32)> {
+        let app_state = self.app_state.read().unwrap();
+        let scale = &app_state.scale;
+        let root = scale.note(degree.rem_euclid(scale.notes().len() as i32));
+
+        let notes = match app_state.play_mode {
+            PlayMode::Chord7 => chord::get7(scale, root),
+            _ => chord::get(scale, root),
+        };
+
+        return notes
+            .iter()
+            .enumerate()
+            .map(|(i, note)| (app_state.channel_for(None, i > 0), *note))
+            .collect();
+    }
+
+    pub fn stop_generator(&mut self) {
+        let notes = self.ledger.remove(&NoteSource::Generator);
+        self.send_notes_off(&notes);
+        self.walker.restart();
+    }
+
+    fn generate(&mut self) {
+        let note = {
+            let app_state = self.app_state.read().unwrap();
+            let scale_length = app_state.scale.notes().len() as i32;
+
+            match self.walker.step(&app_state.generator, scale_length) {
+                Step::Hold => return,
+                Step::Rest => None,
+                Step::Note(position) => Some((
+                    app_state.channel_for(None, false),
+                    app_state.scale.note(position),
+                )),
+            }
+        };
+
+        let previous = self.ledger.remove(&NoteSource::Generator);
+        self.send_notes_off(&previous);
+
+        if let Some(note) = note {
+            self.send_notes_on(&[note], self.note_on_velocity());
+            self.ledger.insert(NoteSource::Generator, vec![note]);
+        }
+    }
+
+    pub fn stop_metronome(&mut self) {
+        let notes = self.ledger.remove(&NoteSource::Metronome);
+        self.send_notes_off(&notes);
+        self.last_grid = None;
+    }
+
+    // Scores a press against the nearest grid line, so presses shortly
+    // before a line count as early rather than very late.
+    pub fn handle_timing(&mut self, at: Instant) {
+        let last_grid = match self.last_grid {
+            Some(last_grid) => last_grid,
+            None => return,
+        };
+
+        let mut app_state = self.app_state.write().unwrap();
+
+        if !app_state.rhythm.enabled {
+            return;
+        }
+
+        let grid = 60_000 / (app_state.tempo as i64 * app_state.rhythm.subdivision as i64);
+
+        let offset = if at >= last_grid {
+            at.duration_since(last_grid).as_millis() as i64
+        } else {
+            -(last_grid.duration_since(at).as_millis() as i64)
+        };
+
+        let offset = if offset > grid / 2 { offset - grid } else { offset };
+
+        app_state.rhythm.record(offset);
+    }
+
+    fn metronome(&mut self, tick: u64, at: Instant) {
+        let (channel, note, subdivision) = {
+            let app_state = self.app_state.read().unwrap();
+            let rhythm = &app_state.rhythm;
+
+            (rhythm.channel, rhythm.note as u32, rhythm.subdivision)
+        };
+
+        if tick.is_multiple_of(TICKS_PER_BEAT / subdivision) {
+            self.last_grid = Some(at);
+        }
+
+        if !tick.is_multiple_of(TICKS_PER_BEAT) {
+            return;
+        }
+
+        let velocity = if tick.is_multiple_of(TICKS_PER_BEAT * 4) { 127 } else { 90 };
+        let click = vec![(channel, note)];
+
+        let previous = self.ledger.remove(&NoteSource::Metronome);
+        self.send_notes_off(&previous);
+        self.send_notes_on(&click, velocity);
+        self.ledger.insert(NoteSource::Metronome, click);
+    }
+
+    pub fn toggle_song(&mut self) {
+        let started = self.app_state.write().unwrap().song_follow.toggle();
+
+        if started {
+            self.show_song_step();
+        }
+    }
+
+    fn show_song_step(&mut self) {
+        let (positions, ticks) = match self.app_state.read().unwrap().song_follow.step() {
+            Some(step) => (step.positions.clone(), step.ticks),
+            None => return,
+        };
+
+        let mut targets: Vec<Key> = Vec::new();
+
+        for position in positions {
+            match self.position_to_key(position) {
+                Some(key) => targets.push(key),
+                None => info!("Song position {} is not on the keyboard", position),
+            }
+        }
+
+        // A step with nothing to press would wait forever.
+        if targets.is_empty() {
+            self.next_song_step();
+            return;
+        }
+
+        self.app_state
+            .write()
+            .unwrap()
+            .song_follow
+            .begin_step(targets);
+
+        self.song_due = self.tick + ticks;
+    }
+
+    pub fn toggle_follow_mode(&mut self) {
+        let ticks = {
+            let mut app_state = self.app_state.write().unwrap();
+            app_state.song_follow.toggle_mode();
+            app_state.song_follow.step().map(|step| step.ticks)
+        };
+
+        if let Some(ticks) = ticks {
+            self.song_due = self.tick + ticks;
+        }
+    }
+
+    fn next_song_step(&mut self) {
+        let more = self.app_state.write().unwrap().song_follow.next();
+
+        if more {
+            self.show_song_step();
+        }
+    }
+
+    fn follow(&mut self, key: &Key) {
+        let (complete, mode) = {
+            let mut app_state = self.app_state.write().unwrap();
+
+            if !app_state.song_follow.active {
+                return;
+            }
+
+            (app_state.song_follow.press(key), app_state.song_follow.mode)
+        };
+
+        if complete && mode == FollowMode::Wait {
+            self.next_song_step();
+        }
+    }
+
+    fn position_to_key(&self, position: i32) -> Option<Key> {
+        return NOTE_KEYS
+            .iter()
+            .find(|key| self.key_to_position((*key).clone()) == Some(position))
+            .cloned();
+    }
+
+    pub fn stop_ear_training(&mut self) {
+        let notes = self.ledger.remove(&NoteSource::EarTrainer);
+        self.send_notes_off(&notes);
+        self.trainer.clear();
+    }
+
+    pub fn replay_question(&mut self) {
+        if self.app_state.read().unwrap().ear_training.question.is_some() {
+            self.trainer.replay(self.tick);
+        }
+    }
+
+    fn answer(&mut self, key: &Key) {
+        let (exercise, question, scale_length) = {
+            let app_state = self.app_state.read().unwrap();
+            let training = &app_state.ear_training;
+
+            match (training.exercise, training.question) {
+                (Some(exercise), Some(question)) => {
+                    (exercise, question, app_state.scale.notes().len() as i32)
+                }
+                _ => return,
+            }
+        };
+
+        let position = self.key_to_position(key.clone());
+
+        let (correct, expected) = match exercise {
+            Exercise::Degree => (
+                position.map(|position| position.rem_euclid(scale_length)) == Some(question),
+                position_key(Row::Lower, question),
+            ),
+            Exercise::Interval => (position == Some(question), position_key(Row::Lower, question)),
+            Exercise::Chord => {
+                let expected = self.app_state.read().unwrap().ear_training.chord_key(question);
+
+                (expected.as_ref() == Some(key), expected)
+            }
+        };
+
+        self.app_state
+            .write()
+            .unwrap()
+            .ear_training
+            .answer(key.clone(), correct, expected);
+
+        self.trainer.schedule(self.tick + TICKS_PER_BEAT * 2);
+    }
+
+    fn train_ear(&mut self, tick: u64) {
+        {
+            let mut app_state = self.app_state.write().unwrap();
+            let app_state = &mut *app_state;
+
+            if app_state.ear_training.question.is_none() && self.trainer.ready(tick) {
+                let question = self
+                    .trainer
+                    .ask(&app_state.ear_training, &app_state.scale, tick);
+
+                app_state.ear_training.question = question;
+            }
+        }
+
+        let notes = match self.trainer.advance(tick) {
+            Some(notes) => notes,
+            None => return,
+        };
+
+        let previous = self.ledger.remove(&NoteSource::EarTrainer);
+        self.send_notes_off(&previous);
+
+        if !notes.is_empty() {
+            let channel = self.app_state.read().unwrap().channel_for(None, false);
+            let notes: Vec<(u8, u32)> = notes.iter().map(|note| (channel, *note)).collect();
+
+            self.send_notes_on(&notes, self.note_on_velocity());
+            self.ledger.insert(NoteSource::EarTrainer, notes);
+        }
+    }
+
+    pub fn stop_improviser(&mut self) {
+        let notes = self.ledger.remove(&NoteSource::Improviser);
+        self.send_notes_off(&notes);
+        self.improvisation.stop();
+    }
+
+    // Learns from the notes coming back from the bus, as long as the ledger
+    // says they were played on the note keys or came in from MIDI input.
+    pub fn learn(&mut self, channel: u8, note: u8) {
+        if !self.app_state.read().unwrap().improviser.enabled || channel == DRUM_CHANNEL {
+            return;
+        }
+
+        let played = match self.ledger.source_of(&(channel, note as u32)) {
+            Some(NoteSource::Key(key)) => key_position(key).is_some(),
+            Some(NoteSource::External(_)) => true,
+            _ => false,
+        };
+
+        if !played {
+            return;
+        }
+
+        let degree = match self.note_to_position(note as u32) {
+            Some(degree) => degree,
+            None => return,
+        };
+
+        if self.improvisation.is_playing() {
+            let notes = self.ledger.remove(&NoteSource::Improviser);
+            self.send_notes_off(&notes);
+        }
+
+        let mut app_state = self.app_state.write().unwrap();
+        self.improvisation
+            .record(degree, self.tick, &mut app_state.improviser.model);
+    }
+
+    fn improvise(&mut self, tick: u64) {
+        let note = {
+            let app_state = self.app_state.read().unwrap();
+
+            match self.improvisation.advance(
+                tick,
+                &app_state.improviser.model,
+                app_state.improviser.order,
+            ) {
+                Step::Hold => return,
+                Step::Rest => None,
+                Step::Note(degree) => Some((
+                    app_state.channel_for(None, false),
+                    app_state.scale.note(degree),
+                )),
+            }
+        };
+
+        let previous = self.ledger.remove(&NoteSource::Improviser);
+        self.send_notes_off(&previous);
+
+        if let Some(note) = note {
+            self.send_notes_on(&[note], self.note_on_velocity());
+            self.ledger.insert(NoteSource::Improviser, vec![note]);
+        }
+    }
+
+    pub fn handle_clock(&mut self, tick: u64, at: Instant) {
+        self.tick = tick;
+
+        if self.app_state.read().unwrap().improviser.enabled {
+            self.improvise(tick);
+        }
+
+        if self.app_state.read().unwrap().ear_training.exercise.is_some() {
+            self.train_ear(tick);
+        }
+
+        if self.app_state.read().unwrap().rhythm.enabled {
+            self.metronome(tick, at);
+        }
+
+        let following = {
+            let app_state = self.app_state.read().unwrap();
+            app_state.song_follow.active && app_state.song_follow.mode == FollowMode::Clock
+        };
+
+        if following && tick >= self.song_due {
+            self.next_song_step();
+        }
+
+        let generate = self.app_state.read().unwrap().generator.enabled;
+
+        if generate && tick.is_multiple_of(TICKS_PER_BEAT / 4) {
+            self.generate();
+        }
+
+        if tick.is_multiple_of(TICKS_PER_BEAT * 4) {
+            let playing = {
+                let app_state = self.app_state.read().unwrap();
+
+                match app_state.progressions.mode {
+                    ProgressionMode::Clock => app_state.progressions.playing(),
+                    ProgressionMode::Step => None,
+                }
+            };
+
+            if let Some(key) = playing {
+                self.advance_progression(key);
+            }
+        }
+
+        let (keys, trigger) = {
+            let app_state = self.app_state.read().unwrap();
+
+            let keys: Vec<Key> = app_state
+                .pressed_keys
+                .iter()
+                .filter(|(key, val)| **val && !app_state.latched_keys.contains(key))
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            (keys, app_state.note_repeat.should_trigger(tick))
+        };
+
+        self.repeats.retain(|key, _| keys.contains(key));
+
+        if !trigger {
+            return;
+        }
+
+        for key in keys {
+            let notes: Vec<(u8, u32)> = self.key_to_midi_notes(key.clone());
+
+            if notes.is_empty() {
+                continue;
+            }
+
+            let source = NoteSource::Key(key.clone());
+            let previous = self.ledger.remove(&source);
+
+            if previous.is_empty() {
+                continue;
+            }
+
+            let count = {
+                let count = self.repeats.entry(key).or_insert(0);
+                *count += 1;
+                *count
+            };
+
+            let velocity = self
+                .app_state
+                .read()
+                .unwrap()
+                .note_repeat
+                .velocity(self.note_on_velocity(), count);
+
+            self.send_notes_off(&previous);
+            self.send_notes_on(&notes, velocity);
+            self.ledger.insert(source, notes);
+        }
+    }
+
+    // Keeps the sounding mono note in the ledger under the key that holds
+    // it, so reset and panic release it like any other key.
+    fn send_mono(
+        &mut self,
+        owner: Option<(Key, (u8, u32))>,
+        on: Option<(u8, u32)>,
+        off: Option<(u8, u32)>,
+    ) {
+        if let Some((key, _)) = owner {
+            self.ledger.remove(&NoteSource::Key(key));
+        }
+
+        self.send_legato(on, off);
+
+        if let Some((key, note)) = self.mono.sounding() {
+            self.ledger.insert(NoteSource::Key(key), vec![note]);
+        }
+    }
+
+    fn send_legato(&self, on: Option<(u8, u32)>, off: Option<(u8, u32)>) {
+        if let Some(note) = on {
+            self.send_notes_on(&[note], self.note_on_velocity());
+        }
+
+        if let Some(note) = off {
+            self.send_notes_off(&[note]);
+        }
+    }
+
+    fn note_on_velocity(&self) -> u8 {
+        return self.app_state.read().unwrap().velocity.note_on();
+    }
+
+    fn send_notes_on(&self, notes: &[(u8, u32)], velocity: u8) {
+        for (channel, note) in notes.iter() {
             self.emitter
                 .send(Event::Note {
                     message: NoteMessage::On,
+                    channel: *channel,
                     note: *note as u8,
-                    velocity: 10,
+                    velocity,
                 })
                 .unwrap_or_default();
         }
+
+        let mut app_state = self.app_state.write().unwrap();
+
+        for (_, note) in notes.iter() {
+            app_state.sustained_notes.remove(note);
+        }
     }
 
-    pub fn handle_key_off(&self, key: Key) {
-        let notes: Vec<u32> = self.key_to_midi_notes(key);
+    fn send_notes_off(&self, notes: &[(u8, u32)]) {
+        let velocity = self.app_state.read().unwrap().velocity.note_off;
 
-        for note in notes.iter() {
+        for (channel, note) in notes.iter() {
             self.emitter
                 .send(Event::Note {
                     message: NoteMessage::Off,
+                    channel: *channel,
                     note: *note as u8,
-                    velocity: 10,
+                    velocity,
                 })
                 .unwrap_or_default();
         }
+
+        let mut app_state = self.app_state.write().unwrap();
+
+        if app_state.sustain {
+            app_state
+                .sustained_notes
+                .extend(notes.iter().map(|(_, note)| note));
+        }
+    }
+
+    fn is_drum_pad(&self, key: &Key) -> bool {
+        return match key_position(key) {
+            Some((row, _)) => self.app_state.read().unwrap().play_mode_for(Some(row)) == PlayMode::Drums,
+            None => false,
+        };
+    }
+
+    fn key_to_midi_notes(&self, key: Key) -> Vec<(u8, u32)> {
+        let row = key_position(&key).map(|(row, _)| row);
+
+        if self.is_drum_pad(&key) {
+            return match self.app_state.read().unwrap().drum_kit.note(&key) {
+                Some(note) => vec![(DRUM_CHANNEL, note as u32)],
+                None => Vec::new(),
+            };
+        }
+
+        return match self.key_to_midi(key) {
+            Some(note) => self.midi_notes(row, note),
+            None => Vec::new(),
+        };
     }
 
-    fn key_to_midi_notes(&self, key: Key) -> Vec<u32> {
+    fn midi_notes(&self, row: Option<Row>, note: u32) -> Vec<(u8, u32)> {
         let app_state = self.app_state.read().unwrap();
-        let note = self.key_to_midi(key);
-
-        match note {
-            Some(note) => {
-                if app_state.play_chord() {
-                    let scale = &app_state.scale;
-                    return chord::get(scale, note);
-                } else if app_state.play_chord7() {
-                    let scale = &app_state.scale;
-                    return chord::get7(scale, note);
-                } else {
-                    return [note].to_vec();
-                }
-            }
-            None => {
-                return Vec::new();
-            }
+        let scale = &app_state.scale;
+
+        let notes = match app_state.play_mode_for(row) {
+            PlayMode::Chord => chord::get(scale, note),
+            PlayMode::Chord7 => chord::get7(scale, note),
+            PlayMode::Single | PlayMode::Mono | PlayMode::Drums => [note].to_vec(),
         };
+
+        let octave = app_state.octave_for(row) * 12;
+
+        return notes
+            .iter()
+            .enumerate()
+            .map(|(i, note)| {
+                let channel = app_state.channel_for(row, i > 0);
+
+                (channel, (*note as i32 + octave).clamp(0, 127) as u32)
+            })
+            .collect();
     }
 
     fn key_to_midi(&self, key: Key) -> Option<u32> {
-        let index = match key {
-            Key::W => 4,
-            Key::E => 5,
-            Key::R => 6,
-            Key::T => 7,
-            Key::Y => 8,
-            Key::U => 9,
-            Key::I => 10,
-            Key::O => 11,
-            Key::A => 0,
-            Key::S => 1,
-            Key::D => 2,
-            Key::F => 3,
-            Key::G => 4,
-            Key::H => 5,
-            Key::J => 6,
-            Key::K => 7,
-            Key::L => 8,
-            _ => -1,
-        };
-
-        if index == -1 {
-            return None;
-        }
+        let index = self.key_to_position(key)?;
+
+        return Some(self.app_state.read().unwrap().scale.note(index));
+    }
+
+    fn note_to_position(&self, note: u32) -> Option<i32> {
+        let app_state = self.app_state.read().unwrap();
+        let scale = &app_state.scale;
+
+        return (-40..80).find(|position| scale.note(*position) == note);
+    }
+
+    fn key_to_position(&self, key: Key) -> Option<i32> {
+        let (row, index) = key_position(&key)?;
 
         let app_state = self.app_state.read().unwrap();
         let scale = &app_state.scale;
 
-        return Some(scale.note(index));
+        return match row {
+            Row::Lower => Some(index),
+            Row::Upper => Some(index + app_state.row_layout.offset(scale.notes().len() as i32)),
+        };
     }
 }
+
+static NOTE_KEYS: [Key; 17] = [
+    Key::A, Key::S, Key::D, Key::F, Key::G, Key::H, Key::J, Key::K, Key::L,
+    Key::W, Key::E, Key::R, Key::T, Key::Y, Key::U, Key::I, Key::O,
+];
+
+pub fn position_key(row: Row, index: i32) -> Option<Key> {
+    return NOTE_KEYS
+        .iter()
+    .find(|key| key_position(key) == Some((row, index)))
+    .cloned();
+}
+
+pub fn key_position(key: &Key) -> Option<(Row, i32)> {
+    return match key {
+        Key::W => Some((Row::Upper, 0)),
+        Key::E => Some((Row::Upper, 1)),
+        Key::R => Some((Row::Upper, 2)),
+        Key::T => Some((Row::Upper, 3)),
+        Key::Y => Some((Row::Upper, 4)),
+        Key::U => Some((Row::Upper, 5)),
+        Key::I => Some((Row::Upper, 6)),
+        Key::O => Some((Row::Upper, 7)),
+        Key::A => Some((Row::Lower, 0)),
+        Key::S => Some((Row::Lower, 1)),
+        Key::D => Some((Row::Lower, 2)),
+        Key::F => Some((Row::Lower, 3)),
+        Key::G => Some((Row::Lower, 4)),
+        Key::H => Some((Row::Lower, 5)),
+        Key::J => Some((Row::Lower, 6)),
+        Key::K => Some((Row::Lower, 7)),
+        Key::L => Some((Row::Lower, 8)),
+        _ => None,
+    };
+}
@@ -0,0 +1,48 @@
+use crate::events::Key;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum NoteSource {
+    Key(Key),
+    External(u8),
+    Generator,
+    Improviser,
+    EarTrainer,
+    Metronome,
+}
+
+pub struct NoteLedger {
+    notes: HashMap<NoteSource, Vec<(u8, u32)>>,
+}
+
+impl NoteLedger {
+    pub fn new() -> Self {
+        return NoteLedger {
+            notes: HashMap::new(),
+        };
+    }
+
+    pub fn insert(&mut self, source: NoteSource, notes: Vec<(u8, u32)>) {
+        self.notes.insert(source, notes);
+    }
+
+    pub fn remove(&mut self, source: &NoteSource) -> Vec<(u8, u32)> {
+        return self.notes.remove(source).unwrap_or_default();
+    }
+
+    pub fn source_of(&self, note: &(u8, u32)) -> Option<&NoteSource> {
+        return self
+            .notes
+            .iter()
+            .find(|(_, notes)| notes.contains(note))
+            .map(|(source, _)| source);
+    }
+
+    pub fn sources(&self) -> Vec<NoteSource> {
+        return self.notes.keys().cloned().collect();
+    }
+
+    pub fn drain(&mut self) -> Vec<(u8, u32)> {
+        return self.notes.drain().flat_map(|(_, notes)| notes).collect();
+    }
+}
@@ -1,12 +1,18 @@
+mod ear_trainer;
+mod generator;
+mod improviser;
 mod keyboard_handler;
+mod ledger;
+mod mono;
 
 use crate::app_state::*;
 use crate::events::*;
 use crate::scale::*;
 use std::sync::*;
 use std::thread;
+use std::time::Instant;
 
-pub use keyboard_handler::KeyboardHandler;
+pub use keyboard_handler::{midi_to_note, KeyboardHandler};
 
 pub struct Player {
     handle: thread::JoinHandle<()>,
@@ -16,17 +22,51 @@ impl Player {
     pub fn new(app_state: Arc<RwLock<AppState>>, event_bus: &mut EventBus) -> Self {
         let events_recv = event_bus.new_receive();
 
-        let keyboard_handler = KeyboardHandler::new(event_bus.emitter.clone(), app_state.clone());
+        let mut keyboard_handler = KeyboardHandler::new(event_bus.emitter(Source::Player), app_state.clone());
 
         let handle = thread::spawn(move || loop {
             select! {
                 recv(events_recv) -> msg => {
-                    let event = msg.unwrap_or_else({ |_| Event::None });
+                    let (event, at) = match msg {
+                        Ok(envelope) => (envelope.event, envelope.at),
+                        Err(_) => (Event::None, Instant::now()),
+                    };
 
                     match event {
                         Event::Quit { } => {
                             break;
                         }
+                        Event::Clock(tick) => {
+                            keyboard_handler.handle_clock(tick, at);
+                        }
+                        Event::Note {
+                            message: NoteMessage::On,
+                            channel,
+                            note,
+                            ..
+                        } => {
+                            keyboard_handler.learn(channel, note);
+                        }
+                        Event::ExternalNote {
+                            message,
+                            note,
+                            velocity,
+                        } => {
+                            keyboard_handler.handle_external(message, note, velocity);
+                        }
+                        Event::Bend(amount) => {
+                            keyboard_handler.set_bend(amount);
+                        }
+                        Event::Modulation(amount) => {
+                            keyboard_handler.set_modulation(amount);
+                        }
+                        Event::Velocity(amount) => {
+                            let mut app_state = app_state.write().unwrap();
+                            app_state.velocity.set_level(amount);
+                        }
+                        Event::Expression(amount) => {
+                            keyboard_handler.set_expression(amount);
+                        }
                         Event::KeyDown(key) => {
                             match key {
                                 Key::Num1 {} => {
@@ -91,11 +131,172 @@ impl Player {
                                     scale.increase_root(12);
                                     app_state.pressed_keys.insert(key, true);
                                 }
-                                Key::Space {} => {
+                                Key::Tab {} => {
                                     keyboard_handler.reset();
                                     let mut app_state = app_state.write().unwrap();
                                     app_state.toggle_play_mode();
                                 }
+                                Key::Minus {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.velocity.decrease(8);
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Equals {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.velocity.increase(8);
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::LeftBracket {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.velocity.decrease_note_off(8);
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::RightBracket {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.velocity.increase_note_off(8);
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Backslash {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.velocity.toggle_curve();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F1 {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.humanize.toggle();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F4 {} => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.row_layout.toggle();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Comma {} => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.routing.decrease_channel();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Period {} => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.routing.increase_channel();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Quote {} => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.routing.toggle_preset();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Escape {} => {
+                                    keyboard_handler.panic();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F9 {} => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.mono.toggle_priority();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F10 {} => {
+                                    app_state.write().unwrap().mono.toggle_portamento();
+                                    keyboard_handler.set_portamento();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F11 {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.polyphony.toggle_limit();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F12 {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.polyphony.toggle_policy();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F8 {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.input_mapping.toggle();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F5 {} => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.split.toggle();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F6 {} | Key::F7 {} if app_state.read().unwrap().split.enabled => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+                                    let row = if key == Key::F6 { Row::Lower } else { Row::Upper };
+
+                                    if let Some(zone) = app_state.split.zone_mut(row) {
+                                        zone.toggle_play_mode();
+                                    }
+
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Home {} | Key::End {} if app_state.read().unwrap().split.enabled => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+
+                                    if let Some(zone) = app_state.split.zone_mut(Row::Lower) {
+                                        if key == Key::Home {
+                                            zone.decrease_octave();
+                                        } else {
+                                            zone.increase_octave();
+                                        }
+                                    }
+
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::PageDown {} | Key::PageUp {} if app_state.read().unwrap().split.enabled => {
+                                    keyboard_handler.reset();
+                                    let mut app_state = app_state.write().unwrap();
+
+                                    if let Some(zone) = app_state.split.zone_mut(Row::Upper) {
+                                        if key == Key::PageDown {
+                                            zone.decrease_octave();
+                                        } else {
+                                            zone.increase_octave();
+                                        }
+                                    }
+
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F2 {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.note_repeat.toggle_rate();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::F3 {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.note_repeat.toggle_ramp();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Up {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.increase_tempo(5);
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Down {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.decrease_tempo(5);
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Shift {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.velocity.accent = true;
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Space {} => {
+                                    keyboard_handler.set_sustain(true);
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
                                 Key::C {} => {
                                     keyboard_handler.reset();
                                     let mut app_state = app_state.write().unwrap();
@@ -112,12 +313,147 @@ impl Player {
                                 | Key::S | Key::D | Key::F
                                 | Key::G | Key::H | Key::J
                                 | Key::K | Key::L => {
-                                    keyboard_handler.handle_key_on(key.clone());
+                                    keyboard_handler.handle_timing(at);
+                                    let latch = app_state.read().unwrap().latch();
+
+                                    if latch {
+                                        keyboard_handler.handle_latch(key.clone());
+                                    } else {
+                                        keyboard_handler.handle_key_on(key.clone());
+                                    }
+
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::NumLock {} => {
+                                    let enabled = {
+                                        let mut app_state = app_state.write().unwrap();
+                                        app_state.rhythm.toggle();
+                                        app_state.pressed_keys.insert(key, true);
+                                        app_state.rhythm.enabled
+                                    };
+
+                                    if !enabled {
+                                        keyboard_handler.stop_metronome();
+                                    }
+                                }
+                                Key::PrintScreen {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.rhythm.toggle_subdivision();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Pause {} => {
+                                    keyboard_handler.toggle_song();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::ScrollLock {} => {
+                                    keyboard_handler.toggle_follow_mode();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Ctrl {} => {
+                                    keyboard_handler.stop_ear_training();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.ear_training.toggle();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::CapsLock {} => {
+                                    keyboard_handler.replay_question();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Enter {} => {
+                                    let enabled = {
+                                        let mut app_state = app_state.write().unwrap();
+                                        app_state.improviser.toggle();
+                                        app_state.pressed_keys.insert(key, true);
+                                        app_state.improviser.enabled
+                                    };
+
+                                    if !enabled {
+                                        keyboard_handler.stop_improviser();
+                                    }
+                                }
+                                Key::Backspace {} => {
                                     let mut app_state = app_state.write().unwrap();
+                                    app_state.improviser.toggle_order();
                                     app_state.pressed_keys.insert(key, true);
                                 }
+                                Key::Insert {} => {
+                                    let enabled = {
+                                        let mut app_state = app_state.write().unwrap();
+                                        app_state.generator.toggle();
+                                        app_state.pressed_keys.insert(key, true);
+                                        app_state.generator.enabled
+                                    };
+
+                                    if !enabled {
+                                        keyboard_handler.stop_generator();
+                                    }
+                                }
+                                Key::Delete {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.generator.toggle_steps();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Left {} | Key::Right {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    let shift = app_state.pressed_keys.contains_key(&Key::Shift);
+
+                                    match (shift, key == Key::Left) {
+                                        (true, true) => app_state.generator.narrow_span(),
+                                        (true, false) => app_state.generator.widen_span(),
+                                        (false, true) => app_state.generator.lower_register(),
+                                        (false, false) => app_state.generator.raise_register(),
+                                    }
+
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Semicolon {} | Key::Slash {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    let shift = app_state.pressed_keys.contains_key(&Key::Shift);
+
+                                    match (shift, key == Key::Semicolon) {
+                                        (true, true) => app_state.generator.decrease_rests(),
+                                        (true, false) => app_state.generator.increase_rests(),
+                                        (false, true) => app_state.generator.decrease_density(),
+                                        (false, false) => app_state.generator.increase_density(),
+                                    }
+
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::Backquote {} => {
+                                    keyboard_handler.stop_progression();
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.progressions.toggle_mode();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                key if app_state.read().unwrap().is_progression_pad(&key) => {
+                                    keyboard_handler.handle_progression(key.clone());
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.insert(key, true);
+                                }
+                                Key::P {} => {
+                                    let latch = {
+                                        let mut app_state = app_state.write().unwrap();
+                                        app_state.toggle_latch_mode();
+                                        app_state.pressed_keys.insert(key, true);
+                                        app_state.latch()
+                                    };
+
+                                    if !latch {
+                                        keyboard_handler.release_latched();
+                                    }
+                                }
                                 _ =>  {
                                     let mut app_state = app_state.write().unwrap();
+                                    let channel = app_state.routing.channel;
+
+                                    for event in app_state.bindings.trigger(&key, channel) {
+                                        keyboard_handler.emitter.send(event).unwrap_or_default();
+                                    }
+
                                     app_state.pressed_keys.insert(key, true);
                                 }
                             }
@@ -130,7 +466,38 @@ impl Player {
                                 | Key::S | Key::D | Key::F
                                 | Key::G | Key::H | Key::J
                                 | Key::K | Key::L => {
-                                    keyboard_handler.handle_key_off(key.clone());
+                                    let latched =
+                                        app_state.read().unwrap().latched_keys.contains(&key);
+
+                                    if !latched {
+                                        keyboard_handler.handle_key_off(key.clone());
+                                    }
+
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.remove(&key);
+                                }
+                                Key::Alt {} => {
+                                    keyboard_handler.set_bend(0.0);
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.remove(&key);
+                                }
+                                Key::Shift {} => {
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.velocity.accent = false;
+                                    app_state.pressed_keys.remove(&key);
+                                }
+                                key if app_state.read().unwrap().is_progression_pad(&key) => {
+                                    let step = app_state.read().unwrap().progressions.mode == ProgressionMode::Step;
+
+                                    if step {
+                                        keyboard_handler.release_progression(key.clone());
+                                    }
+
+                                    let mut app_state = app_state.write().unwrap();
+                                    app_state.pressed_keys.remove(&key);
+                                }
+                                Key::Space {} => {
+                                    keyboard_handler.set_sustain(false);
                                     let mut app_state = app_state.write().unwrap();
                                     app_state.pressed_keys.remove(&key);
                                 }
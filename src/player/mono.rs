@@ -0,0 +1,119 @@
+use crate::app_state::NotePriority;
+use crate::events::Key;
+
+type Note = (u8, u32);
+
+// The note to start and the note to stop.
+type Transition = (Option<Note>, Option<Note>);
+
+pub struct MonoVoice {
+    held: Vec<(Key, Note)>,
+    sounding: Option<Note>,
+}
+
+impl MonoVoice {
+    pub fn new() -> Self {
+        return MonoVoice {
+            held: Vec::new(),
+            sounding: None,
+        };
+    }
+
+    // Returns the note to start and the note to stop, in that order, so
+    // overlapping presses glide legato instead of retriggering the envelope.
+    pub fn press(
+        &mut self,
+        key: Key,
+        note: Note,
+        priority: NotePriority,
+    ) -> Transition {
+        self.held.retain(|(held, _)| *held != key);
+        self.held.push((key, note));
+
+        return self.transition(priority);
+    }
+
+    pub fn release(
+        &mut self,
+        key: &Key,
+        priority: NotePriority,
+    ) -> Transition {
+        self.held.retain(|(held, _)| held != key);
+
+        return self.transition(priority);
+    }
+
+    // The held key whose note is sounding.
+    pub fn sounding(&self) -> Option<(Key, Note)> {
+        let sounding = self.sounding?;
+
+        return self
+            .held
+            .iter()
+            .find(|(_, note)| *note == sounding)
+            .cloned();
+    }
+
+    pub fn clear(&mut self) -> Option<Note> {
+        self.held.clear();
+
+        return self.sounding.take();
+    }
+
+    fn transition(&mut self, priority: NotePriority) -> Transition {
+        let target = match priority {
+            NotePriority::Last => self.held.last().map(|(_, note)| *note),
+            NotePriority::Lowest => self.held.iter().map(|(_, note)| *note).min_by_key(|n| n.1),
+            NotePriority::Highest => self.held.iter().map(|(_, note)| *note).max_by_key(|n| n.1),
+        };
+
+        if target == self.sounding {
+            return (None, None);
+        }
+
+        let previous = self.sounding;
+        self.sounding = target;
+
+        return (target, previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glides_between_overlapping_presses() {
+        let mut mono = MonoVoice::new();
+
+        assert_eq!(mono.press(Key::Q, (0, 60), NotePriority::Last), (Some((0, 60)), None));
+        assert_eq!(mono.press(Key::W, (0, 62), NotePriority::Last), (Some((0, 62)), Some((0, 60))));
+        assert_eq!(mono.sounding(), Some((Key::W, (0, 62))));
+
+        assert_eq!(mono.release(&Key::W, NotePriority::Last), (Some((0, 60)), Some((0, 62))));
+        assert_eq!(mono.release(&Key::Q, NotePriority::Last), (None, Some((0, 60))));
+        assert_eq!(mono.sounding(), None);
+    }
+
+    #[test]
+    fn follows_note_priority() {
+        let mut mono = MonoVoice::new();
+
+        mono.press(Key::Q, (0, 60), NotePriority::Lowest);
+        assert_eq!(mono.press(Key::W, (0, 64), NotePriority::Lowest), (None, None));
+        assert_eq!(mono.press(Key::E, (0, 55), NotePriority::Lowest), (Some((0, 55)), Some((0, 60))));
+
+        assert_eq!(mono.press(Key::R, (0, 50), NotePriority::Highest), (Some((0, 64)), Some((0, 55))));
+        assert_eq!(mono.release(&Key::Q, NotePriority::Highest), (None, None));
+    }
+
+    #[test]
+    fn clear_returns_sounding_note() {
+        let mut mono = MonoVoice::new();
+        mono.press(Key::Q, (0, 60), NotePriority::Last);
+
+        assert_eq!(mono.clear(), Some((0, 60)));
+        assert_eq!(mono.clear(), None);
+        assert_eq!(mono.release(&Key::Q, NotePriority::Last), (None, None));
+    }
+}
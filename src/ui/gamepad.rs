@@ -0,0 +1,44 @@
+use crate::events;
+use sdl2::controller::{Axis, Button};
+
+static DEAD_ZONE: i32 = 4000;
+
+pub fn button_to_key(button: Button) -> events::Key {
+    return match button {
+        Button::A => events::Key::A,
+        Button::B => events::Key::S,
+        Button::X => events::Key::D,
+        Button::Y => events::Key::F,
+        Button::DPadDown => events::Key::G,
+        Button::DPadRight => events::Key::H,
+        Button::DPadUp => events::Key::J,
+        Button::DPadLeft => events::Key::K,
+        Button::LeftShoulder => events::Key::Z,
+        Button::RightShoulder => events::Key::X,
+        Button::LeftStick => events::Key::Space,
+        Button::Back => events::Key::Tab,
+        Button::Start => events::Key::P,
+        _ => events::Key::None,
+    };
+}
+
+pub fn axis_to_event(axis: Axis, value: i16) -> Option<events::Event> {
+    let at_rest = (value as i32).abs() < DEAD_ZONE;
+    let amount = if at_rest {
+        0.0
+    } else {
+        (value as f32 / 32767.0).max(-1.0)
+    };
+
+    return match axis {
+        Axis::LeftY => Some(events::Event::Bend(-amount)),
+        Axis::RightY => Some(events::Event::Modulation((-amount).max(0.0))),
+        // A resting trigger keeps the velocity set with -/=.
+        Axis::TriggerRight if at_rest => None,
+        Axis::TriggerRight => Some(events::Event::Velocity(amount)),
+        // A released expression trigger restores full volume instead of muting.
+        Axis::TriggerLeft if at_rest => Some(events::Event::Expression(1.0)),
+        Axis::TriggerLeft => Some(events::Event::Expression(amount)),
+        _ => None,
+    };
+}
@@ -1,7 +1,6 @@
 use crate::app_state::*;
 use crate::events;
 use crate::player::KeyboardHandler;
-use crossbeam_channel::Sender;
 use sdl2::pixels::*;
 use sdl2::rect::*;
 use sdl2::render::TextureQuery;
@@ -32,6 +31,7 @@ struct KeyToDraw<'a> {
 }
 
 static ROW_LENGTH: u32 = 10;
+pub static TOP: u32 = 200;
 
 static NUM_ROW: [KeyboardKey; 10] = [
     KeyboardKey {
@@ -133,8 +133,8 @@ static FIRST_ROW: [KeyboardKey; 10] = [
         key: events::Key::O,
     },
     KeyboardKey {
-        label: None,
-        variant: 0,
+        label: Some("latch"),
+        variant: 1,
         key: events::Key::P,
     },
 ];
@@ -226,7 +226,7 @@ static THIRD_ROW: [KeyboardKey; 7] = [
 ];
 
 static SPACE_ROW: KeyboardKey = KeyboardKey {
-    label: Some("Mode"),
+    label: Some("Sustain"),
     variant: 1,
     key: events::Key::Space,
 };
@@ -273,12 +273,17 @@ fn get_centered_rect(
 
 fn get_keyboard_rects<'a>(
     keyboard_handler: &'a KeyboardHandler,
+    app_state: &AppState,
     cons_width: u32,
-    _cons_height: u32,
-) -> (Vec<Rect>, Vec<KeyToDraw<'a>>) {
+    cons_height: u32,
+    top: u32,
+) -> (Vec<(Rect, events::Key)>, Vec<KeyToDraw<'a>>) {
     let spacing = 10;
     let total_spacing = 8 * spacing;
-    let individual_width = (cons_width - total_spacing) / ROW_LENGTH;
+
+    // Five rows of keys have to fit below the labels.
+    let individual_width = ((cons_width - total_spacing) / ROW_LENGTH)
+        .min(cons_height.saturating_sub(4 * spacing) / 5);
 
     let mut res = Vec::new();
     let mut keycode = Vec::new();
@@ -298,13 +303,18 @@ fn get_keyboard_rects<'a>(
             Some(actual_label) => {
                 key_label = Cow::from(*actual_label);
             }
-            None => {
-                key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
-            }
+            None => match app_state.pad_label(key) {
+                Some(binding_label) => {
+                    key_label = Cow::from(binding_label);
+                }
+                None => {
+                    key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
+                }
+            },
         }
 
         let i = i as u32;
-        let target = rect! {20 + (i * individual_width) + (i * spacing), 150, individual_width, individual_width};
+        let target = rect! {20 + (i * individual_width) + (i * spacing), top, individual_width, individual_width};
         let color = color_for_variant(*variant);
         keycode.push(KeyToDraw {
             label: key_label,
@@ -312,7 +322,7 @@ fn get_keyboard_rects<'a>(
             key,
             color,
         });
-        res.push(target);
+        res.push((target, key.clone()));
     }
 
     for (
@@ -330,13 +340,18 @@ fn get_keyboard_rects<'a>(
             Some(actual_label) => {
                 key_label = Cow::from(*actual_label);
             }
-            None => {
-                key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
-            }
+            None => match app_state.pad_label(key) {
+                Some(binding_label) => {
+                    key_label = Cow::from(binding_label);
+                }
+                None => {
+                    key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
+                }
+            },
         }
 
         let i = i as u32;
-        let target = rect! {28 + (i * individual_width) + (i * spacing), 150 + individual_width + spacing, individual_width, individual_width};
+        let target = rect! {28 + (i * individual_width) + (i * spacing), top + individual_width + spacing, individual_width, individual_width};
         let color = color_for_variant(*variant);
         keycode.push(KeyToDraw {
             label: key_label,
@@ -344,7 +359,7 @@ fn get_keyboard_rects<'a>(
             key,
             color,
         });
-        res.push(target);
+        res.push((target, key.clone()));
     }
 
     for (
@@ -362,13 +377,18 @@ fn get_keyboard_rects<'a>(
             Some(actual_label) => {
                 key_label = Cow::from(*actual_label);
             }
-            None => {
-                key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
-            }
+            None => match app_state.pad_label(key) {
+                Some(binding_label) => {
+                    key_label = Cow::from(binding_label);
+                }
+                None => {
+                    key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
+                }
+            },
         }
 
         let i = i as u32;
-        let target = rect! {36 + (i * individual_width) + (i * spacing), 150 + (individual_width + spacing) * 2, individual_width, individual_width};
+        let target = rect! {36 + (i * individual_width) + (i * spacing), top + (individual_width + spacing) * 2, individual_width, individual_width};
         let color = color_for_variant(*variant);
         keycode.push(KeyToDraw {
             label: key_label,
@@ -376,7 +396,7 @@ fn get_keyboard_rects<'a>(
             key,
             color,
         });
-        res.push(target);
+        res.push((target, key.clone()));
     }
 
     for (
@@ -394,13 +414,18 @@ fn get_keyboard_rects<'a>(
             Some(actual_label) => {
                 key_label = Cow::from(*actual_label);
             }
-            None => {
-                key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
-            }
+            None => match app_state.pad_label(key) {
+                Some(binding_label) => {
+                    key_label = Cow::from(binding_label);
+                }
+                None => {
+                    key_label = Cow::from(keyboard_handler.key_to_note(key.clone()));
+                }
+            },
         }
 
         let i = i as u32;
-        let target = rect! {44 + (i * individual_width) + (i * spacing), 150 + (individual_width + spacing) * 3, individual_width, individual_width};
+        let target = rect! {44 + (i * individual_width) + (i * spacing), top + (individual_width + spacing) * 3, individual_width, individual_width};
         let color = color_for_variant(*variant);
         keycode.push(KeyToDraw {
             label: key_label,
@@ -408,11 +433,11 @@ fn get_keyboard_rects<'a>(
             key,
             color,
         });
-        res.push(target);
+        res.push((target, key.clone()));
     }
 
     {
-        let target = rect! {44 + 2 * individual_width + 2 * spacing, 150 + (individual_width + spacing) * 4, 5 * individual_width + 5 * spacing, individual_width};
+        let target = rect! {44 + 2 * individual_width + 2 * spacing, top + (individual_width + spacing) * 4, 5 * individual_width + 5 * spacing, individual_width};
         let color = color_for_variant(SPACE_ROW.variant);
         keycode.push(KeyToDraw {
             label: Cow::from(SPACE_ROW.label.unwrap()),
@@ -420,7 +445,7 @@ fn get_keyboard_rects<'a>(
             key: &SPACE_ROW.key,
             color,
         });
-        res.push(target);
+        res.push((target, SPACE_ROW.key.clone()));
     }
 
     return (res, keycode);
@@ -429,30 +454,44 @@ fn get_keyboard_rects<'a>(
 pub struct KeyboardRenderer {
     keyboard_handler: KeyboardHandler,
     app_state: Arc<RwLock<AppState>>,
+    key_targets: Vec<(Rect, events::Key)>,
 }
 
 impl<'l> KeyboardRenderer {
-    pub fn new(emitter: Sender<events::Event>, app_state: Arc<RwLock<AppState>>) -> Self {
+    pub fn new(emitter: events::Emitter, app_state: Arc<RwLock<AppState>>) -> Self {
         let app_state_clone = app_state.clone();
 
         return Self {
             app_state,
             keyboard_handler: KeyboardHandler::new(emitter, app_state_clone),
+            key_targets: Vec::new(),
         };
     }
 
+    pub fn key_at(&self, x: i32, y: i32) -> Option<events::Key> {
+        return self
+            .key_targets
+            .iter()
+            .find(|(target, _)| target.contains_point((x, y)))
+            .map(|(_, key)| key.clone());
+    }
+
     pub fn render(
         &mut self,
         canvas: &mut WindowCanvas,
         texture_creator: &TextureCreator<WindowContext>,
         font: &Font<'l, 'static>,
+        top: u32,
+        height: u32,
     ) -> Result<(), Box<dyn Error>> {
         let app_state = self.app_state.read().unwrap();
 
-        let (_key_targets, key_draws) = get_keyboard_rects(
+        let (key_targets, key_draws) = get_keyboard_rects(
             &self.keyboard_handler,
+            &app_state,
             app_state.screen_width - 60,
-            app_state.screen_height,
+            height,
+            top,
         );
 
         for KeyToDraw {
@@ -465,6 +504,16 @@ impl<'l> KeyboardRenderer {
             canvas.set_draw_color(color);
             canvas.fill_rect(target)?;
 
+            if app_state.latched_keys.contains(key) {
+                canvas.set_draw_color(Color::RGBA(128, 96, 170, 255));
+                canvas.fill_rect(target)?;
+            }
+
+            if app_state.song_follow.is_target(key) {
+                canvas.set_draw_color(Color::RGBA(224, 176, 72, 255));
+                canvas.fill_rect(target)?;
+            }
+
             match app_state.pressed_keys.get(&key) {
                 Some(true) => {
                     canvas.set_draw_color(Color::RGBA(171, 136, 213, 255));
@@ -473,6 +522,18 @@ impl<'l> KeyboardRenderer {
                 _ => {}
             }
 
+            match app_state.ear_training.flash(key) {
+                Some(true) => {
+                    canvas.set_draw_color(Color::RGBA(86, 180, 120, 255));
+                    canvas.fill_rect(target)?;
+                }
+                Some(false) => {
+                    canvas.set_draw_color(Color::RGBA(210, 78, 90, 255));
+                    canvas.fill_rect(target)?;
+                }
+                None => {}
+            }
+
             let color = Color::RGBA(255, 255, 255, 255);
 
             let surface = font
@@ -497,8 +558,7 @@ impl<'l> KeyboardRenderer {
             canvas.copy(&texture, None, Some(target))?;
         }
 
-        //canvas.set_draw_color(Color::RGBA(0, 255, 255, 255));
-        //canvas.draw_rects(&key_targets)?;
+        self.key_targets = key_targets;
 
         return Ok(());
     }
@@ -1,3 +1,4 @@
+mod gamepad;
 mod keyboard_renderer;
 mod render;
 
@@ -3,18 +3,21 @@ extern crate sdl2;
 use crate::app_state::*;
 use crate::events;
 use crate::events::EventBus;
-//use crate::player::KeyboardHandler;
-use super::keyboard_renderer::KeyboardRenderer;
-use crossbeam_channel::{Receiver, Sender};
+use crate::player::midi_to_note;
+use super::gamepad;
+use super::keyboard_renderer::{KeyboardRenderer, TOP};
+use crossbeam_channel::Receiver;
 use log::info;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::TextureQuery;
+use sdl2::TimerSubsystem;
 use std::error::Error;
 use std::sync::*;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 macro_rules! rect(
     ($x:expr, $y:expr, $w:expr, $h:expr) => (
@@ -22,9 +25,107 @@ macro_rules! rect(
     )
 );
 
+static HISTOGRAM_HEIGHT: u32 = 90;
+
+fn to_key(keycode: Option<Keycode>) -> events::Key {
+    return match keycode {
+        Some(Keycode::Space {}) => events::Key::Space,
+        Some(Keycode::Tab {}) => events::Key::Tab,
+        Some(Keycode::Escape {}) => events::Key::Escape,
+        Some(Keycode::LShift {}) | Some(Keycode::RShift {}) => events::Key::Shift,
+        Some(Keycode::LAlt {}) | Some(Keycode::RAlt {}) => events::Key::Alt,
+        Some(Keycode::Minus {}) => events::Key::Minus,
+        Some(Keycode::Equals {}) => events::Key::Equals,
+        Some(Keycode::LeftBracket {}) => events::Key::LeftBracket,
+        Some(Keycode::RightBracket {}) => events::Key::RightBracket,
+        Some(Keycode::Backslash {}) => events::Key::Backslash,
+        Some(Keycode::F1 {}) => events::Key::F1,
+        Some(Keycode::F2 {}) => events::Key::F2,
+        Some(Keycode::F3 {}) => events::Key::F3,
+        Some(Keycode::F4 {}) => events::Key::F4,
+        Some(Keycode::F5 {}) => events::Key::F5,
+        Some(Keycode::F6 {}) => events::Key::F6,
+        Some(Keycode::F7 {}) => events::Key::F7,
+        Some(Keycode::F8 {}) => events::Key::F8,
+        Some(Keycode::F9 {}) => events::Key::F9,
+        Some(Keycode::F10 {}) => events::Key::F10,
+        Some(Keycode::F11 {}) => events::Key::F11,
+        Some(Keycode::F12 {}) => events::Key::F12,
+        Some(Keycode::Home {}) => events::Key::Home,
+        Some(Keycode::End {}) => events::Key::End,
+        Some(Keycode::PageUp {}) => events::Key::PageUp,
+        Some(Keycode::PageDown {}) => events::Key::PageDown,
+        Some(Keycode::Comma {}) => events::Key::Comma,
+        Some(Keycode::Period {}) => events::Key::Period,
+        Some(Keycode::Quote {}) => events::Key::Quote,
+        Some(Keycode::Backquote {}) => events::Key::Backquote,
+        Some(Keycode::Return {}) => events::Key::Enter,
+        Some(Keycode::Backspace {}) => events::Key::Backspace,
+        Some(Keycode::LCtrl {}) | Some(Keycode::RCtrl {}) => events::Key::Ctrl,
+        Some(Keycode::CapsLock {}) => events::Key::CapsLock,
+        Some(Keycode::Pause {}) => events::Key::Pause,
+        Some(Keycode::ScrollLock {}) => events::Key::ScrollLock,
+        Some(Keycode::NumLockClear {}) => events::Key::NumLock,
+        Some(Keycode::PrintScreen {}) => events::Key::PrintScreen,
+        Some(Keycode::Insert {}) => events::Key::Insert,
+        Some(Keycode::Delete {}) => events::Key::Delete,
+        Some(Keycode::Left {}) => events::Key::Left,
+        Some(Keycode::Right {}) => events::Key::Right,
+        Some(Keycode::Semicolon {}) => events::Key::Semicolon,
+        Some(Keycode::Slash {}) => events::Key::Slash,
+        Some(Keycode::Up {}) => events::Key::Up,
+        Some(Keycode::Down {}) => events::Key::Down,
+        Some(Keycode::Num0 {}) => events::Key::Num0,
+        Some(Keycode::Num1 {}) => events::Key::Num1,
+        Some(Keycode::Num2 {}) => events::Key::Num2,
+        Some(Keycode::Num3 {}) => events::Key::Num3,
+        Some(Keycode::Num4 {}) => events::Key::Num4,
+        Some(Keycode::Num5 {}) => events::Key::Num5,
+        Some(Keycode::Num6 {}) => events::Key::Num6,
+        Some(Keycode::Num7 {}) => events::Key::Num7,
+        Some(Keycode::Num8 {}) => events::Key::Num8,
+        Some(Keycode::Num9 {}) => events::Key::Num9,
+        Some(Keycode::Q {}) => events::Key::Q,
+        Some(Keycode::W {}) => events::Key::W,
+        Some(Keycode::E {}) => events::Key::E,
+        Some(Keycode::R {}) => events::Key::R,
+        Some(Keycode::T {}) => events::Key::T,
+        Some(Keycode::Y {}) => events::Key::Y,
+        Some(Keycode::U {}) => events::Key::U,
+        Some(Keycode::I {}) => events::Key::I,
+        Some(Keycode::O {}) => events::Key::O,
+        Some(Keycode::P {}) => events::Key::P,
+        Some(Keycode::A {}) => events::Key::A,
+        Some(Keycode::S {}) => events::Key::S,
+        Some(Keycode::D {}) => events::Key::D,
+        Some(Keycode::F {}) => events::Key::F,
+        Some(Keycode::G {}) => events::Key::G,
+        Some(Keycode::H {}) => events::Key::H,
+        Some(Keycode::J {}) => events::Key::J,
+        Some(Keycode::K {}) => events::Key::K,
+        Some(Keycode::L {}) => events::Key::L,
+        Some(Keycode::Z {}) => events::Key::Z,
+        Some(Keycode::X {}) => events::Key::X,
+        Some(Keycode::C {}) => events::Key::C,
+        Some(Keycode::V {}) => events::Key::V,
+        Some(Keycode::B {}) => events::Key::B,
+        Some(Keycode::N {}) => events::Key::N,
+        Some(Keycode::M {}) => events::Key::M,
+        _ => events::Key::None,
+    };
+}
+
+// SDL stamps events in milliseconds since it started, which places a press
+// more precisely than reading the clock once per frame.
+fn event_time(timer: &mut TimerSubsystem, timestamp: u32) -> Instant {
+    let age = timer.ticks().saturating_sub(timestamp);
+
+    return Instant::now() - Duration::from_millis(age as u64);
+}
+
 pub struct Render {
-    pub recv: Receiver<events::Event>,
-    pub emitter: Sender<events::Event>,
+    pub recv: Receiver<events::Envelope>,
+    pub emitter: events::Emitter,
     pub app_state: Arc<RwLock<AppState>>,
 }
 
@@ -35,7 +136,7 @@ impl Render {
         return Render {
             app_state,
             recv: events_recv,
-            emitter: event_bus.emitter.clone(),
+            emitter: event_bus.emitter(events::Source::Render),
         };
     }
 
@@ -64,20 +165,71 @@ impl Render {
         let font = ttf_context.load_font("./assets/Roboto/Roboto-Regular.ttf", 20)?;
         let texture_creator = canvas.texture_creator();
 
+        let game_controller_subsystem = match sdl_context.game_controller() {
+            Ok(subsystem) => Some(subsystem),
+            Err(err) => {
+                info!("Running without game controller support: {}", err);
+                None
+            }
+        };
+        let mut controllers = Vec::new();
+
         let mut event_pump = sdl_context.event_pump().unwrap();
+        let mut timer = sdl_context.timer().unwrap();
         let mut keyboard_renderer =
             KeyboardRenderer::new(self.emitter.clone(), self.app_state.clone());
+        let mut mouse_key: Option<events::Key> = None;
 
         'running: loop {
             canvas.set_draw_color(Color::RGB(26, 22, 37));
             canvas.clear();
 
-            {
-                let app_state = self.app_state.write().unwrap();
-                let scale = &app_state.scale;
+            let (left_labels, right_labels) = {
+                let app_state = self.app_state.read().unwrap();
+
+                let sustain_label = if app_state.sustain {
+                    let mut notes: Vec<&u32> = app_state.sustained_notes.iter().collect();
+                    notes.sort();
+
+                    notes.iter().fold(String::from("Sustain"), |label, note| {
+                        format!("{} {}", label, midi_to_note(**note))
+                    })
+                } else {
+                    String::new()
+                };
 
+                let mut left_labels = vec![
+                    app_state.scale.label(),
+                    app_state.tempo_label(),
+                    app_state.humanize.label(),
+                    sustain_label,
+                    app_state.progressions.label(),
+                    app_state.generator.label(),
+                    app_state.improviser.label(),
+                    app_state.ear_training.label(),
+                    app_state.song_follow.label(),
+                    app_state.rhythm.label(),
+                ];
+
+                left_labels.extend(app_state.split.labels());
+
+                (
+                    left_labels,
+                    vec![
+                        app_state.play_mode_label(),
+                        app_state.channel_label(),
+                        app_state.velocity.label(),
+                        app_state.polyphony.label(),
+                        app_state.row_layout.label(),
+                        app_state.latch_mode_label(),
+                        app_state.midi_input_label(),
+                    ],
+                )
+            };
+
+            for (i, label) in left_labels.iter().filter(|l| !l.is_empty()).enumerate() {
                 let surface = font
-                    .render(&scale.label())
+                    .render(label)
                     .blended(Color::RGBA(255, 255, 255, 255))
                     .map_err(|e| e.to_string())?;
                 let texture = texture_creator
@@ -85,16 +237,14 @@ impl Render {
                     .map_err(|e| e.to_string())?;
 
                 let TextureQuery { width, height, .. } = texture.query();
-                let target = rect!(20, 20, width, height);
+                let target = rect!(20, 20 + i * 30, width, height);
 
                 canvas.copy(&texture, None, Some(target))?;
             }
 
-            {
-                let app_state = self.app_state.write().unwrap();
-
+            for (i, label) in right_labels.iter().filter(|l| !l.is_empty()).enumerate() {
                 let surface = font
-                    .render(&app_state.play_mode_label())
+                    .render(label)
                     .blended(Color::RGBA(255, 255, 255, 255))
                     .map_err(|e| e.to_string())?;
                 let texture = texture_creator
@@ -102,12 +252,45 @@ impl Render {
                     .map_err(|e| e.to_string())?;
 
                 let TextureQuery { width, height, .. } = texture.query();
-                let target = rect!(screen_width - 20 - width, 20, width, height);
+                let target = rect!(screen_width - 20 - width, 20 + i * 30, width, height);
 
                 canvas.copy(&texture, None, Some(target))?;
             }
 
-            keyboard_renderer.render(&mut canvas, &texture_creator, &font)?;
+            // The keyboard moves down when there are more labels than fit
+            // above it, and shrinks to keep clear of the histogram.
+            let label_rows = left_labels
+                .iter()
+                .filter(|l| !l.is_empty())
+                .count()
+                .max(right_labels.iter().filter(|l| !l.is_empty()).count()) as u32;
+            let top = TOP.max(30 + label_rows * 30);
+            let height = screen_height.saturating_sub(top + HISTOGRAM_HEIGHT + 20);
+
+            keyboard_renderer.render(&mut canvas, &texture_creator, &font, top, height)?;
+
+            {
+                let app_state = self.app_state.read().unwrap();
+
+                if app_state.rhythm.enabled {
+                    let histogram = app_state.rhythm.histogram();
+                    let most = *histogram.iter().max().unwrap_or(&0);
+                    let width = 24;
+
+                    for (i, count) in histogram.iter().enumerate() {
+                        let height = (count * HISTOGRAM_HEIGHT).checked_div(most).unwrap_or(0);
+                        let x = (screen_width - histogram.len() as u32 * width) / 2 + i as u32 * width;
+
+                        if i == histogram.len() / 2 {
+                            canvas.set_draw_color(Color::RGBA(86, 180, 120, 255));
+                        } else {
+                            canvas.set_draw_color(Color::RGBA(171, 136, 213, 255));
+                        }
+
+                        canvas.fill_rect(rect!(x + 2, screen_height - 10 - height, width - 4, height.max(1)))?;
+                    }
+                }
+            }
 
             for event in event_pump.poll_iter() {
                 match event {
@@ -119,96 +302,113 @@ impl Render {
                         break 'running;
                     }
                     Event::KeyDown {
+                        timestamp,
                         repeat: false,
                         keycode,
                         ..
                     } => {
-                        let key = match keycode {
-                            Some(Keycode::Space {}) => events::Key::Space,
-                            Some(Keycode::Num0 {}) => events::Key::Num0,
-                            Some(Keycode::Num1 {}) => events::Key::Num1,
-                            Some(Keycode::Num2 {}) => events::Key::Num2,
-                            Some(Keycode::Num3 {}) => events::Key::Num3,
-                            Some(Keycode::Num4 {}) => events::Key::Num4,
-                            Some(Keycode::Num5 {}) => events::Key::Num5,
-                            Some(Keycode::Num6 {}) => events::Key::Num6,
-                            Some(Keycode::Num7 {}) => events::Key::Num7,
-                            Some(Keycode::Num8 {}) => events::Key::Num8,
-                            Some(Keycode::Q {}) => events::Key::Q,
-                            Some(Keycode::W {}) => events::Key::W,
-                            Some(Keycode::E {}) => events::Key::E,
-                            Some(Keycode::R {}) => events::Key::R,
-                            Some(Keycode::T {}) => events::Key::T,
-                            Some(Keycode::Y {}) => events::Key::Y,
-                            Some(Keycode::U {}) => events::Key::U,
-                            Some(Keycode::I {}) => events::Key::I,
-                            Some(Keycode::O {}) => events::Key::O,
-                            Some(Keycode::A {}) => events::Key::A,
-                            Some(Keycode::S {}) => events::Key::S,
-                            Some(Keycode::D {}) => events::Key::D,
-                            Some(Keycode::F {}) => events::Key::F,
-                            Some(Keycode::G {}) => events::Key::G,
-                            Some(Keycode::H {}) => events::Key::H,
-                            Some(Keycode::J {}) => events::Key::J,
-                            Some(Keycode::K {}) => events::Key::K,
-                            Some(Keycode::L {}) => events::Key::L,
-                            Some(Keycode::Z {}) => events::Key::Z,
-                            Some(Keycode::X {}) => events::Key::X,
-                            Some(Keycode::C {}) => events::Key::C,
-                            Some(Keycode::V {}) => events::Key::V,
-                            Some(Keycode::B {}) => events::Key::B,
-                            Some(Keycode::N {}) => events::Key::N,
-                            Some(Keycode::M {}) => events::Key::M,
-                            _ => events::Key::None,
-                        };
-
-                        self.emitter.send(events::Event::KeyDown(key))?;
+                        let at = event_time(&mut timer, timestamp);
+                        self.emitter.send_at(events::Event::KeyDown(to_key(keycode)), at)?;
                     }
-
                     Event::KeyUp {
+                        timestamp,
                         repeat: false,
                         keycode,
                         ..
                     } => {
-                        let key = match keycode {
-                            Some(Keycode::Num0 {}) => events::Key::Num0,
-                            Some(Keycode::Num1 {}) => events::Key::Num1,
-                            Some(Keycode::Num2 {}) => events::Key::Num2,
-                            Some(Keycode::Num3 {}) => events::Key::Num3,
-                            Some(Keycode::Num4 {}) => events::Key::Num4,
-                            Some(Keycode::Num5 {}) => events::Key::Num5,
-                            Some(Keycode::Num6 {}) => events::Key::Num6,
-                            Some(Keycode::Num7 {}) => events::Key::Num7,
-                            Some(Keycode::Num8 {}) => events::Key::Num8,
-                            Some(Keycode::Q {}) => events::Key::Q,
-                            Some(Keycode::W {}) => events::Key::W,
-                            Some(Keycode::E {}) => events::Key::E,
-                            Some(Keycode::R {}) => events::Key::R,
-                            Some(Keycode::T {}) => events::Key::T,
-                            Some(Keycode::Y {}) => events::Key::Y,
-                            Some(Keycode::U {}) => events::Key::U,
-                            Some(Keycode::I {}) => events::Key::I,
-                            Some(Keycode::O {}) => events::Key::O,
-                            Some(Keycode::A {}) => events::Key::A,
-                            Some(Keycode::S {}) => events::Key::S,
-                            Some(Keycode::D {}) => events::Key::D,
-                            Some(Keycode::F {}) => events::Key::F,
-                            Some(Keycode::G {}) => events::Key::G,
-                            Some(Keycode::H {}) => events::Key::H,
-                            Some(Keycode::J {}) => events::Key::J,
-                            Some(Keycode::K {}) => events::Key::K,
-                            Some(Keycode::L {}) => events::Key::L,
-                            Some(Keycode::Z {}) => events::Key::Z,
-                            Some(Keycode::X {}) => events::Key::X,
-                            Some(Keycode::C {}) => events::Key::C,
-                            Some(Keycode::V {}) => events::Key::V,
-                            Some(Keycode::B {}) => events::Key::B,
-                            Some(Keycode::N {}) => events::Key::N,
-                            Some(Keycode::M {}) => events::Key::M,
-                            _ => events::Key::None,
-                        };
-
-                        self.emitter.send(events::Event::KeyUp(key))?;
+                        let at = event_time(&mut timer, timestamp);
+                        self.emitter.send_at(events::Event::KeyUp(to_key(keycode)), at)?;
+                    }
+                    Event::MouseMotion { x, y, .. }
+                        if sdl_context
+                            .keyboard()
+                            .mod_state()
+                            .intersects(Mod::LALTMOD | Mod::RALTMOD) =>
+                    {
+                        let bend = 1.0 - 2.0 * y as f32 / screen_height as f32;
+                        let modulation = x as f32 / screen_width as f32;
+
+                        self.emitter.send(events::Event::Bend(bend))?;
+                        self.emitter.send(events::Event::Modulation(modulation))?;
+                    }
+                    Event::ControllerDeviceAdded { which, .. } => {
+                        if let Some(subsystem) = &game_controller_subsystem {
+                            match subsystem.open(which) {
+                                Ok(controller) => {
+                                    info!("Opened controller {}", controller.name());
+                                    controllers.push(controller);
+                                }
+                                Err(err) => info!("Could not open controller {}: {}", which, err),
+                            }
+                        }
+                    }
+                    Event::ControllerDeviceRemoved { which, .. } => {
+                        controllers.retain(|controller| controller.instance_id() != which);
+                    }
+                    Event::ControllerButtonDown {
+                        timestamp, button, ..
+                    } => {
+                        let key = gamepad::button_to_key(button);
+                        let at = event_time(&mut timer, timestamp);
+                        self.emitter.send_at(events::Event::KeyDown(key), at)?;
+                    }
+                    Event::ControllerButtonUp {
+                        timestamp, button, ..
+                    } => {
+                        let key = gamepad::button_to_key(button);
+                        let at = event_time(&mut timer, timestamp);
+                        self.emitter.send_at(events::Event::KeyUp(key), at)?;
+                    }
+                    Event::ControllerAxisMotion { axis, value, .. } => {
+                        if let Some(event) = gamepad::axis_to_event(axis, value) {
+                            self.emitter.send(event)?;
+                        }
+                    }
+                    Event::MouseButtonDown {
+                        timestamp,
+                        mouse_btn: MouseButton::Left,
+                        x,
+                        y,
+                        ..
+                    } => {
+                        mouse_key = keyboard_renderer.key_at(x, y);
+
+                        if let Some(key) = mouse_key.clone() {
+                            let at = event_time(&mut timer, timestamp);
+                            self.emitter.send_at(events::Event::KeyDown(key), at)?;
+                        }
+                    }
+                    Event::MouseMotion {
+                        timestamp,
+                        mousestate,
+                        x,
+                        y,
+                        ..
+                    } if mousestate.left() => {
+                        let key = keyboard_renderer.key_at(x, y);
+                        let at = event_time(&mut timer, timestamp);
+
+                        if key != mouse_key {
+                            if let Some(previous) = mouse_key.take() {
+                                self.emitter.send_at(events::Event::KeyUp(previous), at)?;
+                            }
+
+                            if let Some(key) = key.clone() {
+                                self.emitter.send_at(events::Event::KeyDown(key), at)?;
+                            }
+
+                            mouse_key = key;
+                        }
+                    }
+                    Event::MouseButtonUp {
+                        timestamp,
+                        mouse_btn: MouseButton::Left,
+                        ..
+                    } => {
+                        if let Some(key) = mouse_key.take() {
+                            let at = event_time(&mut timer, timestamp);
+                            self.emitter.send_at(events::Event::KeyUp(key), at)?;
+                        }
                     }
                     _ => (),
                 };